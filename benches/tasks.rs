@@ -0,0 +1,122 @@
+use bevy_app::App;
+use bevy_ecs::system::SystemState;
+use bevy_wasm_tasks::{TaskContext, Tasks, TasksPlugin};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn app() -> App {
+    let mut app = App::new();
+    app.add_plugins(TasksPlugin::default());
+    app
+}
+
+/// Spawns batches of empty tasks and waits for all of them to finish.
+fn spawn_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn");
+    for count in [1, 100, 1000] {
+        group.throughput(Throughput::Elements(count));
+        group.bench_with_input(
+            BenchmarkId::new("spawn_tokio", count),
+            &count,
+            |b, &count| {
+                let mut app = app();
+                let mut state = SystemState::<Tasks>::new(app.world_mut());
+                b.iter(|| {
+                    let tasks = state.get(app.world());
+                    let handles: Vec<_> = (0..count)
+                        .map(|_| tasks.spawn_tokio(|_ctx| async {}))
+                        .collect();
+                    tasks.runtime().block_on(async {
+                        for mut handle in handles {
+                            handle.join().await;
+                        }
+                    });
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+/// Measures how long a main thread callback takes to be executed and report back, when it's queued
+/// behind a varying number of other callbacks.
+fn main_thread_round_trip(c: &mut Criterion) {
+    let mut group = c.benchmark_group("main_thread_round_trip");
+    for depth in [0, 100, 1000] {
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &depth, |b, &depth| {
+            let mut app = app();
+            let context = app.world().resource::<TaskContext>().clone();
+            b.iter(|| {
+                for _ in 0..depth {
+                    context.submit_on_main_thread(|_| ());
+                }
+                let mut output_rx = context.submit_on_main_thread(|ctx| ctx.current_tick);
+                loop {
+                    app.update();
+                    if output_rx.try_recv().is_ok() {
+                        break;
+                    }
+                }
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Measures the cost of draining queued callbacks in the per-schedule pump.
+fn pump_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pump_dispatch");
+    for count in [1, 100, 10_000] {
+        group.throughput(Throughput::Elements(count));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &count, |b, &count| {
+            let mut app = app();
+            let context = app.world().resource::<TaskContext>().clone();
+            b.iter(|| {
+                for _ in 0..count {
+                    context.submit_on_main_thread(|_| ());
+                }
+                app.update();
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Measures how quickly callbacks can be queued from several threads at once, and then drained.
+fn submit_throughput(c: &mut Criterion) {
+    const PER_THREAD: u64 = 1000;
+    let mut group = c.benchmark_group("submit_throughput");
+    for threads in [1, 4, 8] {
+        group.throughput(Throughput::Elements(threads * PER_THREAD));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                let mut app = app();
+                let context = app.world().resource::<TaskContext>().clone();
+                b.iter(|| {
+                    std::thread::scope(|scope| {
+                        for _ in 0..threads {
+                            let context = context.clone();
+                            scope.spawn(move || {
+                                for _ in 0..PER_THREAD {
+                                    context.submit_on_main_thread(|_| ());
+                                }
+                            });
+                        }
+                    });
+                    app.update();
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    spawn_throughput,
+    main_thread_round_trip,
+    pump_dispatch,
+    submit_throughput
+);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+use bevy_app::AppExit;
+use bevy_ecs::{
+    event::EventReader,
+    system::{Res, Resource},
+};
+
+/// Publishes the first [`AppExit`] event seen by the app to background tasks, which can wait on it
+/// using [`TaskContext::on_app_exit`](crate::TaskContext::on_app_exit).
+#[derive(Resource)]
+pub struct AppExitSignal {
+    exit_tx: tokio::sync::watch::Sender<Option<AppExit>>,
+}
+
+impl Default for AppExitSignal {
+    fn default() -> Self {
+        Self {
+            exit_tx: tokio::sync::watch::channel(None).0,
+        }
+    }
+}
+
+impl AppExitSignal {
+    pub fn exit_rx(&self) -> tokio::sync::watch::Receiver<Option<AppExit>> {
+        self.exit_tx.subscribe()
+    }
+
+    /// The [`AppExit`] event which the app is exiting with, if it's exiting.
+    pub fn exit(&self) -> Option<AppExit> {
+        self.exit_tx.borrow().clone()
+    }
+
+    pub(crate) fn publish_system(mut events: EventReader<AppExit>, signal: Res<AppExitSignal>) {
+        if let Some(exit) = events.read().last() {
+            signal.exit_tx.send_if_modified(|current| {
+                if current.is_some() {
+                    return false;
+                }
+                *current = Some(exit.clone());
+                true
+            });
+        }
+    }
+}
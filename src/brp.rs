@@ -0,0 +1,189 @@
+use crate::{TaskContext, Tasks};
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::{
+    entity::Entity,
+    event::{Event, EventReader},
+    system::{Res, Resource},
+};
+use serde_json::{json, Value};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// An error returned by a Bevy Remote Protocol call.
+#[derive(Clone, Debug)]
+pub enum BrpError {
+    /// The HTTP request failed or the response wasn't valid JSON-RPC.
+    Transport(String),
+    /// The remote app returned a JSON-RPC error.
+    Remote {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+}
+
+impl std::fmt::Display for BrpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transport(message) => write!(f, "BRP transport error: {message}"),
+            Self::Remote { code, message, .. } => write!(f, "BRP error {code}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for BrpError {}
+
+/// An async client for a remote Bevy app serving the Bevy Remote Protocol over HTTP.
+#[derive(Clone, Debug)]
+pub struct BrpClient {
+    url: Arc<str>,
+    client: reqwest::Client,
+}
+
+impl BrpClient {
+    pub fn new(url: impl AsRef<str>) -> Self {
+        Self {
+            url: url.as_ref().into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Sends a raw JSON-RPC request and returns its `result`.
+    pub async fn request(&self, method: &str, params: Option<Value>) -> Result<Value, BrpError> {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let mut body = json!({
+            "jsonrpc": "2.0",
+            "id": NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+        });
+        if let Some(params) = params {
+            body["params"] = params;
+        }
+        let transport = |err: reqwest::Error| BrpError::Transport(err.to_string());
+        let mut response: Value = self
+            .client
+            .post(&*self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(transport)?
+            .json()
+            .await
+            .map_err(transport)?;
+        if let Some(error) = response.get_mut("error") {
+            return Err(BrpError::Remote {
+                code: error["code"].as_i64().unwrap_or_default(),
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+                data: error.get_mut("data").map(Value::take),
+            });
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(BrpError::Transport(
+                "response contained neither a result nor an error".to_string(),
+            )),
+        }
+    }
+
+    /// `bevy/get`: fetches the given components, by type path, from a remote entity.
+    pub async fn get(&self, entity: Entity, components: &[&str]) -> Result<Value, BrpError> {
+        self.request(
+            "bevy/get",
+            Some(json!({ "entity": entity.to_bits(), "components": components })),
+        )
+        .await
+    }
+
+    /// `bevy/insert`: inserts components, given as a map from type path to value, on a remote entity.
+    pub async fn insert(&self, entity: Entity, components: Value) -> Result<Value, BrpError> {
+        self.request(
+            "bevy/insert",
+            Some(json!({ "entity": entity.to_bits(), "components": components })),
+        )
+        .await
+    }
+
+    /// `bevy/query`: runs a query on the remote app. `params` is the method's `data`/`filter`
+    /// object as described by the protocol.
+    pub async fn query(&self, params: Value) -> Result<Value, BrpError> {
+        self.request("bevy/query", Some(params)).await
+    }
+}
+
+impl TaskContext {
+    /// Returns a Bevy Remote Protocol client for the remote app at `url`.
+    pub fn brp(&self, url: impl AsRef<str>) -> BrpClient {
+        BrpClient::new(url)
+    }
+}
+
+/// A Bevy Remote Protocol call to be made by the [`BrpPlugin`] client. The result arrives as a
+/// [`BrpResponse`] with the same `id`.
+#[derive(Event, Clone, Debug)]
+pub struct BrpRequest {
+    pub id: u64,
+    pub method: String,
+    pub params: Option<Value>,
+}
+
+impl BrpRequest {
+    pub fn new(method: impl Into<String>, params: Option<Value>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct BrpResponse {
+    pub id: u64,
+    pub result: Result<Value, BrpError>,
+}
+
+/// The client used by [`BrpPlugin`], available for direct use from systems and tasks.
+#[derive(Resource, Clone, Debug)]
+pub struct Brp(pub BrpClient);
+
+/// Sends each [`BrpRequest`] event to a remote Bevy app and delivers the outcome as a
+/// [`BrpResponse`] event. Requires the [`TasksPlugin`](crate::TasksPlugin) to be added first, and
+/// on wasm the `wasm` feature.
+pub struct BrpPlugin {
+    pub url: String,
+}
+
+impl BrpPlugin {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    fn send_requests(mut requests: EventReader<BrpRequest>, brp: Res<Brp>, tasks: Tasks) {
+        for request in requests.read().cloned() {
+            let client = brp.0.clone();
+            let task = |ctx: TaskContext| async move {
+                let result = client.request(&request.method, request.params).await;
+                let id = request.id;
+                ctx.submit_on_main_thread(move |ctx| {
+                    ctx.world.send_event(BrpResponse { id, result });
+                });
+            };
+            #[cfg(target_arch = "wasm32")]
+            tasks.spawn_wasm(task);
+            #[cfg(not(target_arch = "wasm32"))]
+            tasks.spawn_auto(task);
+        }
+    }
+}
+
+impl Plugin for BrpPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Brp(BrpClient::new(&self.url)))
+            .add_event::<BrpRequest>()
+            .add_event::<BrpResponse>()
+            .add_systems(Update, Self::send_requests);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{TaskContext, Tasks};
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::event::{Event, EventReader};
+
+/// An error reported by the platform clipboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClipboardError(pub String);
+
+impl std::fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "clipboard error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+impl TaskContext {
+    /// Reads text from the system clipboard. On wasm this uses the asynchronous browser Clipboard
+    /// API, which may prompt the user for permission.
+    pub async fn clipboard_read(&self) -> Result<String, ClipboardError> {
+        platform::read().await
+    }
+
+    /// Writes text to the system clipboard.
+    pub async fn clipboard_write(&self, text: impl Into<String>) -> Result<(), ClipboardError> {
+        platform::write(text.into()).await
+    }
+}
+
+/// A request for the [`ClipboardPlugin`] to read or write the system clipboard.
+#[derive(Event, Clone, Debug)]
+pub enum ClipboardRequest {
+    Read,
+    Write(String),
+}
+
+/// Sent with the outcome of each [`ClipboardRequest`]. For writes, the text is the text which was
+/// written.
+#[derive(Event, Clone, Debug)]
+pub struct ClipboardResult {
+    pub request: ClipboardRequest,
+    pub text: Result<String, ClipboardError>,
+}
+
+/// Services [`ClipboardRequest`] events in background tasks and reports back with
+/// [`ClipboardResult`] events. Requires the [`TasksPlugin`](crate::TasksPlugin) to be added first,
+/// and on wasm the `wasm` feature.
+pub struct ClipboardPlugin;
+
+impl ClipboardPlugin {
+    fn handle_requests(mut requests: EventReader<ClipboardRequest>, tasks: Tasks) {
+        for request in requests.read().cloned() {
+            let task = |ctx: TaskContext| async move {
+                let text = match &request {
+                    ClipboardRequest::Read => ctx.clipboard_read().await,
+                    ClipboardRequest::Write(text) => ctx
+                        .clipboard_write(text.clone())
+                        .await
+                        .map(|()| text.clone()),
+                };
+                ctx.submit_on_main_thread(move |ctx| {
+                    ctx.world.send_event(ClipboardResult { request, text });
+                });
+            };
+            // Browser clipboard futures can't be moved between threads.
+            #[cfg(target_arch = "wasm32")]
+            tasks.spawn_wasm(task);
+            #[cfg(not(target_arch = "wasm32"))]
+            tasks.spawn_auto(task);
+        }
+    }
+}
+
+impl Plugin for ClipboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ClipboardRequest>()
+            .add_event::<ClipboardResult>()
+            .add_systems(Update, Self::handle_requests);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod platform {
+    use super::ClipboardError;
+
+    fn clipboard() -> Result<arboard::Clipboard, ClipboardError> {
+        arboard::Clipboard::new().map_err(|err| ClipboardError(err.to_string()))
+    }
+
+    pub(super) async fn read() -> Result<String, ClipboardError> {
+        clipboard()?
+            .get_text()
+            .map_err(|err| ClipboardError(err.to_string()))
+    }
+
+    pub(super) async fn write(text: String) -> Result<(), ClipboardError> {
+        clipboard()?
+            .set_text(text)
+            .map_err(|err| ClipboardError(err.to_string()))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod platform {
+    use super::ClipboardError;
+    use wasm_bindgen_futures::JsFuture;
+
+    fn clipboard() -> Result<web_sys::Clipboard, ClipboardError> {
+        web_sys::window()
+            .map(|window| window.navigator().clipboard())
+            .ok_or_else(|| ClipboardError("no browser window is available".to_string()))
+    }
+
+    fn js_error(err: wasm_bindgen::JsValue) -> ClipboardError {
+        ClipboardError(format!("{err:?}"))
+    }
+
+    pub(super) async fn read() -> Result<String, ClipboardError> {
+        let text = JsFuture::from(clipboard()?.read_text())
+            .await
+            .map_err(js_error)?;
+        text.as_string()
+            .ok_or_else(|| ClipboardError("clipboard contents were not text".to_string()))
+    }
+
+    pub(super) async fn write(text: String) -> Result<(), ClipboardError> {
+        JsFuture::from(clipboard()?.write_text(&text))
+            .await
+            .map(|_| ())
+            .map_err(js_error)
+    }
+}
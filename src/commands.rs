@@ -0,0 +1,91 @@
+use crate::TaskContext;
+use bevy_ecs::{bundle::Bundle, entity::Entity, event::Event, world::World};
+
+type TaskCommand = Box<dyn FnOnce(&mut World, &mut Vec<Entity>) + Send + 'static>;
+
+/// A buffer of world changes recorded by a task, created by [`TaskContext::commands`]. Nothing is
+/// applied until [`flush`](Self::flush) is called, which makes every recorded change in a single
+/// main thread callback. Commands on entities which no longer exist are skipped.
+pub struct TaskCommands<'a> {
+    ctx: &'a TaskContext,
+    commands: Vec<TaskCommand>,
+}
+
+impl<'a> TaskCommands<'a> {
+    pub(crate) fn new(ctx: &'a TaskContext) -> Self {
+        Self {
+            ctx,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Spawns an entity with `bundle`. Its id is returned by [`flush`](Self::flush).
+    pub fn spawn(&mut self, bundle: impl Bundle) -> &mut Self {
+        self.add(move |world, spawned| spawned.push(world.spawn(bundle).id()))
+    }
+
+    pub fn insert(&mut self, entity: Entity, bundle: impl Bundle) -> &mut Self {
+        self.add(move |world, _| {
+            if let Some(mut entity) = world.get_entity_mut(entity) {
+                entity.insert(bundle);
+            }
+        })
+    }
+
+    pub fn remove<B: Bundle>(&mut self, entity: Entity) -> &mut Self {
+        self.add(move |world, _| {
+            if let Some(mut entity) = world.get_entity_mut(entity) {
+                entity.remove::<B>();
+            }
+        })
+    }
+
+    pub fn despawn(&mut self, entity: Entity) -> &mut Self {
+        self.add(move |world, _| {
+            world.despawn(entity);
+        })
+    }
+
+    pub fn send_event<E: Event>(&mut self, event: E) -> &mut Self {
+        self.add(move |world, _| {
+            world.send_event(event);
+        })
+    }
+
+    /// Records an arbitrary change to the world.
+    pub fn push(&mut self, command: impl FnOnce(&mut World) + Send + 'static) -> &mut Self {
+        self.add(move |world, _| command(world))
+    }
+
+    /// The number of commands recorded since the last flush.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Applies every recorded command in order and waits for them to be applied, returning the ids
+    /// of the entities spawned by [`spawn`](Self::spawn) in the order they were recorded.
+    pub async fn flush(&mut self) -> Vec<Entity> {
+        let commands = std::mem::take(&mut self.commands);
+        self.ctx
+            .run_on_main_thread(move |ctx| {
+                let mut spawned = Vec::new();
+                for command in commands {
+                    command(ctx.world, &mut spawned);
+                }
+                spawned
+            })
+            .await
+    }
+
+    fn add(
+        &mut self,
+        command: impl FnOnce(&mut World, &mut Vec<Entity>) + Send + 'static,
+    ) -> &mut Self {
+        self.commands.push(Box::new(command));
+        self
+    }
+}
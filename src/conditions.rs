@@ -0,0 +1,46 @@
+use bevy_ecs::{entity::Entity, system::Resource, world::World};
+
+/// The entity a task was waiting on was despawned, returned by
+/// [`TaskContext::wait_for_component`](crate::TaskContext::wait_for_component).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct EntityDespawned(pub Entity);
+
+impl std::fmt::Display for EntityDespawned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "entity {} was despawned", self.0)
+    }
+}
+
+impl std::error::Error for EntityDespawned {}
+
+/// A condition a task is waiting on. Returns `true` once the condition has resolved or its task has
+/// stopped waiting, after which it's dropped.
+type Waiter = Box<dyn FnMut(&mut World) -> bool + Send + 'static>;
+
+/// Conditions over the world which tasks are waiting on, evaluated once per frame.
+#[derive(Resource, Default)]
+pub(crate) struct WorldWaiters {
+    waiters: Vec<Waiter>,
+}
+
+impl WorldWaiters {
+    /// Evaluates the waiter immediately, and then once per frame until it resolves.
+    pub(crate) fn add(
+        world: &mut World,
+        mut waiter: impl FnMut(&mut World) -> bool + Send + 'static,
+    ) {
+        if !waiter(world) {
+            world
+                .get_resource_or_insert_with(Self::default)
+                .waiters
+                .push(Box::new(waiter));
+        }
+    }
+
+    pub(crate) fn run_system(world: &mut World) {
+        let mut waiters = std::mem::take(&mut world.resource_mut::<Self>().waiters);
+        waiters.retain_mut(|waiter| !waiter(world));
+        // Waiters may have added more waiters while they ran.
+        world.resource_mut::<Self>().waiters.append(&mut waiters);
+    }
+}
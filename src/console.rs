@@ -0,0 +1,92 @@
+use crate::Tasks;
+use bevy_app::{App, Plugin, Startup};
+use bevy_ecs::event::Event;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+/// A command line entered on the process's standard input, parsed into a command name and
+/// whitespace-separated arguments. Responses written with [`ConsoleCommand::respond`] are printed
+/// back to standard output.
+#[derive(Event, Clone, Debug)]
+pub struct ConsoleCommand {
+    /// The full line as it was entered, without the trailing newline.
+    pub line: String,
+    /// The first word of the line.
+    pub name: String,
+    /// The remaining words of the line.
+    pub args: Vec<String>,
+    responder: ConsoleResponder,
+}
+
+impl ConsoleCommand {
+    fn parse(line: String, responder: ConsoleResponder) -> Option<Self> {
+        let mut words = line.split_whitespace().map(str::to_owned);
+        let name = words.next()?;
+        let args = words.collect();
+        Some(Self {
+            line,
+            name,
+            args,
+            responder,
+        })
+    }
+
+    /// Prints a line of output back to the console.
+    pub fn respond(&self, text: impl Into<String>) {
+        self.responder.respond(text);
+    }
+
+    /// A handle for responding to this command later, e.g. from a background task.
+    pub fn responder(&self) -> ConsoleResponder {
+        self.responder.clone()
+    }
+}
+
+/// Sends lines of output back to the console which a [`ConsoleCommand`] was read from.
+#[derive(Clone, Debug)]
+pub struct ConsoleResponder(tokio::sync::mpsc::UnboundedSender<String>);
+
+impl ConsoleResponder {
+    pub fn respond(&self, text: impl Into<String>) {
+        // The console output task only stops when the runtime does.
+        let _ = self.0.send(text.into());
+    }
+}
+
+/// Spawns background tasks which read commands from standard input and emit them as
+/// [`ConsoleCommand`] events, giving headless servers an interactive admin console. Requires the
+/// [`TasksPlugin`](crate::TasksPlugin) to be added first.
+pub struct ConsolePlugin;
+
+impl ConsolePlugin {
+    fn spawn_console(tasks: Tasks) {
+        let (response_tx, mut response_rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        tasks.spawn_tokio(|_ctx| async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(mut response) = response_rx.recv().await {
+                response.push('\n');
+                if stdout.write_all(response.as_bytes()).await.is_err() {
+                    break;
+                }
+                let _ = stdout.flush().await;
+            }
+        });
+        let responder = ConsoleResponder(response_tx);
+        tasks.spawn_tokio(|ctx| async move {
+            let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Some(command) = ConsoleCommand::parse(line, responder.clone()) {
+                    ctx.submit_on_main_thread(move |ctx| {
+                        ctx.world.send_event(command);
+                    });
+                }
+            }
+        });
+    }
+}
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ConsoleCommand>()
+            .add_systems(Startup, Self::spawn_console);
+    }
+}
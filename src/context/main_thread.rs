@@ -1,20 +1,57 @@
+use crate::pump::{self, InlineWorld};
+use crate::task_channels::{CallbackTicket, Priority};
+use crate::{JoinHandle, Tasks};
 use bevy_app::{First, Last, PostUpdate, PreUpdate, Update};
 use bevy_ecs::{
     schedule::{InternedScheduleLabel, ScheduleLabel},
-    system::{SystemParam, SystemState},
+    system::{
+        Commands, IntoSystem, RegisteredSystemError, Resource, RunSystemOnce, SystemId,
+        SystemParam, SystemState,
+    },
     world::World,
 };
+use bevy_utils::{Duration, HashMap, Instant};
+use std::{
+    any::{Any, TypeId},
+    future::Future,
+    ptr::NonNull,
+    sync::Arc,
+};
 
 pub type MainThreadCallback = Box<dyn FnOnce(MainThreadContext) + Send + 'static>;
 
+/// A callback which only reads the world, run by a non-exclusive system.
+pub type ReadOnlyCallback = Box<dyn FnOnce(&World) + Send + 'static>;
+
+/// Stands in for the schedule main thread callbacks run in when none is given, which is [`Update`]
+/// unless changed with
+/// [`TasksPlugin::with_default_callback_schedule`](crate::TasksPlugin::with_default_callback_schedule).
+#[derive(ScheduleLabel, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DefaultCallbackSchedule;
+
+#[derive(Clone)]
 pub struct MainThreadRunConfiguration {
     pub schedule: InternedScheduleLabel,
+    /// Tickets of callbacks which must have run before this one does. The callback waits in its
+    /// queue, across frames if needed, until all of them have executed.
+    pub after: Vec<CallbackTicket>,
+    /// Which lane of the schedule's queue the callback waits in.
+    pub priority: Priority,
+    /// If set, the callback is held back until the update tick count reaches this value.
+    pub run_at_tick: Option<usize>,
+    /// If set, the callback is skipped when a newer callback with the same key is queued before the
+    /// pump gets to it.
+    pub coalesce_key: Option<Arc<str>>,
 }
 
 impl Default for MainThreadRunConfiguration {
     fn default() -> Self {
         Self {
-            schedule: Update.intern(),
+            schedule: DefaultCallbackSchedule.intern(),
+            after: Vec::new(),
+            priority: Priority::Normal,
+            run_at_tick: None,
+            coalesce_key: None,
         }
     }
 }
@@ -29,6 +66,32 @@ impl MainThreadRunConfiguration {
         self
     }
 
+    /// Makes the callback run only after the callback which returned `ticket` has run.
+    pub fn after(mut self, ticket: CallbackTicket) -> Self {
+        self.after.push(ticket);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Holds the callback back until the update tick count reaches `tick`, so it lines up with a known
+    /// frame. Callbacks for ticks which have already passed run as soon as possible.
+    pub fn at_tick(mut self, tick: usize) -> Self {
+        self.run_at_tick = Some(tick);
+        self
+    }
+
+    /// Only runs the newest of the callbacks with this key which are queued when the pump runs, e.g.
+    /// for "update the preview mesh with the newest result" style submissions where older results
+    /// are stale.
+    pub fn with_coalesce_key(mut self, key: impl Into<Arc<str>>) -> Self {
+        self.coalesce_key = Some(key.into());
+        self
+    }
+
     pub fn on_first() -> Self {
         Self::new_with_schedule(First)
     }
@@ -50,6 +113,80 @@ impl MainThreadRunConfiguration {
     }
 }
 
+/// Why [`try_run_on_main_thread`](crate::TaskContext::try_run_on_main_thread) couldn't get an
+/// output from its callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MainThreadError {
+    /// The callback couldn't be queued.
+    SubmitFailed,
+    /// The callback was discarded before it ran, e.g. because the queues were cleared by
+    /// [`ResetTasks`](crate::ResetTasks).
+    Dropped,
+    /// The app exited, or was torn down, before the callback ran.
+    AppExited,
+}
+
+impl std::fmt::Display for MainThreadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SubmitFailed => write!(f, "failed to queue callback for the main thread"),
+            Self::Dropped => write!(f, "main thread callback was discarded before it ran"),
+            Self::AppExited => write!(f, "app exited before the main thread callback ran"),
+        }
+    }
+}
+
+impl std::error::Error for MainThreadError {}
+
+/// How long [`run_on_main_thread_with_timeout`](crate::TaskContext::run_on_main_thread_with_timeout)
+/// waits for its callback to run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeoutAfter {
+    /// A number of update ticks.
+    Frames(usize),
+    /// A length of wall-clock time. This is checked once per update, so it can only time out while
+    /// the app is still updating.
+    Duration(Duration),
+}
+
+impl From<Duration> for TimeoutAfter {
+    fn from(timeout: Duration) -> Self {
+        TimeoutAfter::Duration(timeout)
+    }
+}
+
+/// Returned when a main thread callback didn't run within its timeout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout;
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "main thread callback timed out")
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// The [`SystemState`]s built by [`MainThreadContext::run`], kept so that callbacks which keep using
+/// the same parameters don't rebuild their access and archetype caches every time.
+#[derive(Resource, Default)]
+pub(crate) struct SystemStateCache {
+    states: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl SystemStateCache {
+    /// Takes the cached state out while it's in use, so a nested `run` with the same parameters
+    /// builds its own rather than aliasing it.
+    fn take<P: SystemParam + 'static>(&mut self) -> Option<SystemState<P>> {
+        let state = self.states.remove(&TypeId::of::<P>())?;
+        state.downcast().ok().map(|state| *state)
+    }
+
+    fn put<P: SystemParam + 'static>(&mut self, state: SystemState<P>) {
+        self.states.insert(TypeId::of::<P>(), Box::new(state));
+    }
+}
+
 /// The context arguments which are available to main thread callbacks requested using
 /// [`run_on_main_thread`](TaskContext::run_on_main_thread).
 pub struct MainThreadContext<'a> {
@@ -57,19 +194,134 @@ pub struct MainThreadContext<'a> {
     pub world: &'a mut World,
     /// The current update tick in which the current main thread callback is executing.
     pub current_tick: usize,
+    /// The schedule whose callbacks are being run.
+    pub schedule: InternedScheduleLabel,
+    /// When the pump's [`time_budget`](crate::PumpConfig::time_budget) for this frame runs out, if
+    /// it has one.
+    pub budget_deadline: Option<Instant>,
 }
 
 impl<'a> MainThreadContext<'a> {
+    /// Borrows this context for a shorter lifetime, e.g. to hand it to several nested callbacks in
+    /// turn.
+    pub fn reborrow(&mut self) -> MainThreadContext<'_> {
+        MainThreadContext {
+            world: &mut *self.world,
+            current_tick: self.current_tick,
+            schedule: self.schedule,
+            budget_deadline: self.budget_deadline,
+        }
+    }
+
+    /// How much of the pump's time budget is left this frame, or `None` if it has no budget. Long
+    /// operations can use this to do part of their work and resubmit themselves for the rest.
+    pub fn remaining_budget(&self) -> Option<Duration> {
+        self.budget_deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
     pub fn run<P, F, Output>(&mut self, f: F) -> Output
     where
         P: SystemParam + 'static,
         F: FnOnce(P::Item<'_, '_>) -> Output,
         Output: Send + 'static,
     {
-        let mut state = SystemState::<P>::new(self.world);
+        let mut state = self
+            .world
+            .get_resource_mut::<SystemStateCache>()
+            .and_then(|mut cache| cache.take::<P>())
+            .unwrap_or_else(|| SystemState::<P>::new(self.world));
         let data = state.get_mut(self.world);
         let output = f(data);
         state.apply(self.world);
+        if let Some(mut cache) = self.world.get_resource_mut::<SystemStateCache>() {
+            cache.put(state);
+        }
         output
     }
+
+    /// Runs `f` with [`Commands`] bound to the world, and applies them as soon as it returns, so
+    /// callbacks can use the familiar commands API rather than mutating the world directly.
+    pub fn commands<F, Output>(&mut self, f: F) -> Output
+    where
+        F: FnOnce(Commands) -> Output,
+        Output: Send + 'static,
+    {
+        self.run::<Commands<'static, 'static>, _, _>(f)
+    }
+
+    /// Runs a system registered with `World::register_system`, so callbacks can reuse logic which
+    /// already lives in a system.
+    pub fn run_system<Output: 'static>(
+        &mut self,
+        id: SystemId<(), Output>,
+    ) -> Result<Output, RegisteredSystemError<(), Output>> {
+        self.world.run_system(id)
+    }
+
+    pub fn run_system_with_input<Input: 'static, Output: 'static>(
+        &mut self,
+        id: SystemId<Input, Output>,
+        input: Input,
+    ) -> Result<Output, RegisteredSystemError<Input, Output>> {
+        self.world.run_system_with_input(id, input)
+    }
+
+    /// Runs a system which hasn't been registered, once. Its state is thrown away afterwards, so
+    /// prefer [`run_system`](Self::run_system) for systems which are run repeatedly.
+    pub fn run_system_once<S, Output, Marker>(&mut self, system: S) -> Output
+    where
+        S: IntoSystem<(), Output, Marker>,
+    {
+        self.world.run_system_once(system)
+    }
+
+    /// Blocks this callback until `future` completes, lending it the world: main thread callbacks
+    /// for this schedule which the future awaits on this thread, e.g. through
+    /// [`TaskContext::with_resource_mut`](crate::TaskContext::with_resource_mut), run inline rather
+    /// than deadlocking. Callbacks submitted from other threads are still queued, so blocking on a
+    /// task which runs elsewhere and waits on the main thread still deadlocks.
+    pub fn block_on<F: Future>(&mut self, future: F) -> F::Output {
+        let inline = InlineWorld {
+            world: NonNull::from(&mut *self.world),
+            current_tick: self.current_tick,
+            schedule: self.schedule,
+            budget_deadline: self.budget_deadline,
+        };
+        pump::with_inline_world(inline, || futures_executor::block_on(future))
+    }
+
+    /// Returns a [`TaskContext`](crate::TaskContext), so that helpers which use the main thread
+    /// bridge can be called from inside a callback.
+    ///
+    /// Callbacks submitted from inside a running callback without waiting on them are queued. Ones
+    /// for the schedule whose callbacks are being run are picked up later in the same drain, unless
+    /// the pump runs out of its budget, in which case they carry over to the next frame. Ones for
+    /// other schedules run when those schedules' pumps next run, later this frame or in the next one.
+    ///
+    /// Awaiting [`run_on_main_thread`](crate::TaskContext::run_on_main_thread) from inside a
+    /// callback by blocking on it with [`block_on`](Self::block_on) runs the nested callback inline
+    /// instead. Blocking on anything which only happens in a later frame deadlocks, since the pump
+    /// can't move on until the callback returns. Helpers such as
+    /// [`wait_until`](crate::TaskContext::wait_until) and
+    /// [`sleep_updates`](crate::TaskContext::sleep_updates) panic in that case, but blocking on the
+    /// receiver returned by [`submit_on_main_thread`](crate::TaskContext::submit_on_main_thread)
+    /// hangs.
+    pub fn task_context(&self) -> crate::TaskContext {
+        self.world.resource::<crate::TaskContext>().clone()
+    }
+
+    /// Spawns a follow-up background task like [`Tasks::spawn_auto`](crate::Tasks::spawn_auto),
+    /// e.g. to kick off the next async stage once this callback has applied the last one's results.
+    pub fn spawn_auto<Task, Output, Spawnable>(
+        &mut self,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(crate::TaskContext) -> Task + 'static,
+    {
+        self.run::<Tasks<'static>, _, _>(move |tasks| tasks.spawn_auto(spawnable_task))
+    }
 }
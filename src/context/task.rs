@@ -1,20 +1,116 @@
-use super::main_thread::{MainThreadContext, MainThreadRunConfiguration};
-use crate::task_channels::TaskChannels;
-use bevy_ecs::system::Resource;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use super::main_thread::{
+    MainThreadContext, MainThreadError, MainThreadRunConfiguration, Timeout, TimeoutAfter,
+};
+use crate::commands::TaskCommands;
+use crate::conditions::{EntityDespawned, WorldWaiters};
+use crate::entity_commands::TaskEntityCommands;
+use crate::events::{EventListeners, EventStream, DEFAULT_EVENT_STREAM_CAPACITY};
+use crate::extract::Extract;
+use crate::inbox::Inbox;
+use crate::mirror::{Mirrored, MirroredResources};
+use crate::pump;
+use crate::query::OwnedQueryData;
+use crate::task_channels::{CallbackTicket, QueuedCallback, TaskChannels};
+use crate::task_id::TaskId;
+use crate::ticks::TickSleeper;
+use crate::timers::{MainThreadTimers, RepeatBy, RepeatHandle, Repeating};
+use crate::typed_channel::TypedChannels;
+use bevy_app::AppExit;
+use bevy_ecs::{
+    bundle::Bundle,
+    component::{Component, Tick},
+    entity::Entity,
+    event::Event,
+    observer::TriggerTargets,
+    query::QueryFilter,
+    schedule::ScheduleLabel,
+    system::{Res, ResMut, Resource},
+    world::World,
+};
+use bevy_utils::{Duration, Instant};
+use futures_util::future::Either;
+use std::any::Any;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::oneshot::Receiver;
+use tokio::sync::{mpsc::UnboundedSender, oneshot::Receiver, OwnedSemaphorePermit};
 
 /// The context arguments which are available to background tasks spawned onto the
 /// [`TasksRuntime`].
 #[derive(Resource, Clone)]
 pub struct TaskContext {
-    pub tick_rx: tokio::sync::watch::Receiver<()>,
+    /// Notified on every update while `tick_sleepers` is non-zero. Wait on it through
+    /// [`next_tick`](Self::next_tick) or [`sleep_updates`](Self::sleep_updates), which register as
+    /// sleepers.
+    pub(crate) tick_rx: tokio::sync::watch::Receiver<()>,
     pub task_channels: TaskChannels,
     pub ticks: Arc<AtomicUsize>,
+    /// The id of the task this context was handed to, if it was created for a spawned task.
+    pub task_id: Option<TaskId>,
+    /// The human-readable name the task was spawned with, if any.
+    pub task_name: Option<Arc<str>>,
+    pub app_exit_rx: tokio::sync::watch::Receiver<Option<AppExit>>,
+    pub world_change_tick: Arc<AtomicU32>,
+    /// How many tasks are waiting on `tick_rx`. Ticks are only broadcast while this is non-zero.
+    pub(crate) tick_sleepers: Arc<AtomicUsize>,
+    /// The task's typed mailbox, if it was spawned with
+    /// [`spawn_with_inbox`](crate::Tasks::spawn_with_inbox).
+    pub inbox: Option<Arc<dyn Any + Send + Sync>>,
+    /// The channels registered with [`init_task_channel`](crate::InitTaskChannel::init_task_channel).
+    pub typed_channels: TypedChannels,
+    /// The resources mirrored with
+    /// [`mirror_resource_to_tasks`](crate::MirrorResourceToTasks::mirror_resource_to_tasks).
+    pub mirrors: MirroredResources,
 }
 
 impl TaskContext {
+    /// Returns the id of the task this context belongs to, or `None` for contexts which were not
+    /// handed to a task spawned through [`Tasks`](crate::Tasks).
+    pub fn task_id(&self) -> Option<TaskId> {
+        self.task_id
+    }
+
+    /// Returns the name the task was spawned with through one of the `_named` spawn variants, such
+    /// as [`spawn_auto_named`](crate::Tasks::spawn_auto_named).
+    pub fn task_name(&self) -> Option<&str> {
+        self.task_name.as_deref()
+    }
+
+    /// Returns the task's typed mailbox, or `None` if the task wasn't spawned with
+    /// [`spawn_with_inbox`](crate::Tasks::spawn_with_inbox) for messages of type `M`.
+    pub fn inbox<M: Send + 'static>(&self) -> Option<Inbox<M>> {
+        Inbox::from_any(self.inbox.clone()?)
+    }
+
+    /// Returns the receiving end of the channel of messages of type `T` which systems send with
+    /// [`TaskSender<T>`](crate::TaskSender), or `None` if
+    /// [`init_task_channel::<T>`](crate::InitTaskChannel::init_task_channel) wasn't called. Each
+    /// message is received by only one of the tasks reading the channel.
+    pub fn channel_inbox<T: Send + 'static>(&self) -> Option<Inbox<T>> {
+        self.typed_channels.inbox()
+    }
+
+    /// Returns a sender of messages of type `T` which systems receive with
+    /// [`TaskReceiver<T>`](crate::TaskReceiver), or `None` if
+    /// [`init_task_channel::<T>`](crate::InitTaskChannel::init_task_channel) wasn't called.
+    pub fn channel_sender<T: Send + 'static>(&self) -> Option<UnboundedSender<T>> {
+        self.typed_channels.sender()
+    }
+
+    /// Returns the task-side view of resource `R`, which is kept up to date without a main thread
+    /// round trip, or `None` if
+    /// [`mirror_resource_to_tasks::<R>`](crate::MirrorResourceToTasks::mirror_resource_to_tasks)
+    /// wasn't called.
+    pub fn mirrored<R: Resource + Clone>(&self) -> Option<Mirrored<R>> {
+        self.mirrors.get()
+    }
+
+    /// Publishes a new value for resource `R`, which is inserted into the world at the start of the
+    /// next frame. Only the latest value published before then is applied. Hands the value back if
+    /// [`apply_from_tasks::<R>`](crate::ApplyFromTasks::apply_from_tasks) wasn't called.
+    pub fn publish<R: Resource>(&self, value: R) -> Result<(), R> {
+        self.mirrors.publish(value)
+    }
+
     /// Returns the current value of the ticket count from the main thread - how many updates
     /// have occurred since the start of the program. Because the tick count is updated from the
     /// main thread, the tick count may change any time after this function call returns.
@@ -22,16 +118,39 @@ impl TaskContext {
         self.ticks.load(Ordering::SeqCst)
     }
 
+    /// Returns the main world's change tick as of the end of the most recent update. Tasks caching
+    /// world data can compare this against the tick their cache was built at to cheaply detect that
+    /// the world may have changed, before paying for a main thread round trip.
+    pub fn world_change_tick(&self) -> Tick {
+        Tick::new(self.world_change_tick.load(Ordering::SeqCst))
+    }
+
     /// Sleeps the background task until a given number of main thread updates have occurred. If
     /// you instead want to sleep for a given length of wall-clock time, sleep using tokio sleep or similar.
     /// function.
     pub async fn sleep_updates(&mut self, updates_to_sleep: usize) {
+        self.wait_for_tick(updates_to_sleep).await;
+    }
+
+    /// Waits until the next main thread update has happened.
+    pub async fn next_tick(&self) {
+        self.wait_for_tick(1).await;
+    }
+
+    async fn wait_for_tick(&self, updates_to_sleep: usize) {
+        pump::assert_not_in_callback();
+        let _sleeper = TickSleeper::register(&self.tick_sleepers);
+        let mut tick_rx = self.tick_rx.clone();
         let target_tick = self
             .ticks
             .load(Ordering::SeqCst)
             .wrapping_add(updates_to_sleep);
-        while self.ticks.load(Ordering::SeqCst) < target_tick {
-            if self.tick_rx.changed().await.is_err() {
+        loop {
+            tick_rx.borrow_and_update();
+            if self.ticks.load(Ordering::SeqCst) >= target_tick {
+                return;
+            }
+            if tick_rx.changed().await.is_err() {
                 return;
             }
         }
@@ -52,8 +171,33 @@ impl TaskContext {
     {
         let (output_tx, output_rx) = tokio::sync::oneshot::channel();
         if self
-            .task_channels
-            .submit(config.schedule, move |ctx| {
+            .enqueue(config, None, move |ctx| {
+                // Allow the sender to drop the output receipt channel.
+                let _ = output_tx.send(runnable(ctx));
+            })
+            .is_err()
+        {
+            panic!("Failed to send operation to be run on main thread");
+        }
+        output_rx
+    }
+
+    /// Like [`submit_on_main_thread_with_config`](Self::submit_on_main_thread_with_config), but also
+    /// returns a [`CallbackTicket`] which later submissions, from this or any other task, can pass to
+    /// [`MainThreadRunConfiguration::after`] to guarantee they run after this callback.
+    pub fn submit_on_main_thread_with_ticket<Runnable, Output>(
+        &self,
+        runnable: Runnable,
+        config: MainThreadRunConfiguration,
+    ) -> (CallbackTicket, Receiver<Output>)
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        let ticket = self.task_channels.issue_ticket();
+        if self
+            .enqueue(config, Some(ticket), move |ctx| {
                 // Allow the sender to drop the output receipt channel.
                 let _ = output_tx.send(runnable(ctx));
             })
@@ -61,9 +205,138 @@ impl TaskContext {
         {
             panic!("Failed to send operation to be run on main thread");
         }
+        (ticket, output_rx)
+    }
+
+    /// Like [`submit_on_main_thread`](Self::submit_on_main_thread), but the callback is only run once
+    /// `delay` has elapsed, without the task having to stay alive to wait for it. The callback is
+    /// held by [`MainThreadTimers`] until it's due.
+    pub fn submit_on_main_thread_after<Runnable, Output>(
+        &self,
+        delay: Duration,
+        runnable: Runnable,
+    ) -> Receiver<Output>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        let deadline = Instant::now() + delay;
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        self.submit_on_main_thread(move |ctx| {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            ctx.world
+                .resource_mut::<MainThreadTimers>()
+                .after(remaining, move |ctx| {
+                    // Allow the sender to drop the output receipt channel.
+                    let _ = output_tx.send(runnable(ctx));
+                });
+        });
         output_rx
     }
 
+    /// Runs the callback on the main thread over and over, every `every` frames or every `every`
+    /// duration, until the returned [`RepeatHandle`] is dropped or cancelled. The task doesn't need
+    /// to stay alive for the callback to keep repeating, only the handle.
+    pub fn submit_repeating<Runnable>(
+        &self,
+        every: impl Into<RepeatBy>,
+        runnable: Runnable,
+    ) -> RepeatHandle
+    where
+        Runnable: FnMut(MainThreadContext) + Send + 'static,
+    {
+        let (repeating, handle) = Repeating::new(every.into(), runnable);
+        self.submit_on_main_thread(move |ctx| {
+            let current_tick = ctx.current_tick;
+            repeating.schedule(
+                &mut ctx.world.resource_mut::<MainThreadTimers>(),
+                current_tick,
+            );
+        });
+        handle
+    }
+
+    /// Like [`submit_on_main_thread`](Self::submit_on_main_thread), but if the callback queues are
+    /// bounded with [`TasksPlugin::with_queue_capacity`](crate::TasksPlugin::with_queue_capacity),
+    /// first waits until there's room in them. Tasks which submit callbacks in bulk should use this,
+    /// so that they slow down rather than filling memory while the main thread is behind.
+    pub async fn submit_on_main_thread_bounded<Runnable, Output>(
+        &self,
+        runnable: Runnable,
+    ) -> Receiver<Output>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        let permit = self.task_channels.reserve().await;
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        if self
+            .enqueue_reserved(Default::default(), None, permit, move |ctx| {
+                // Allow the sender to drop the output receipt channel.
+                let _ = output_tx.send(runnable(ctx));
+            })
+            .is_err()
+        {
+            panic!("Failed to send operation to be run on main thread");
+        }
+        output_rx
+    }
+
+    fn enqueue(
+        &self,
+        config: MainThreadRunConfiguration,
+        ticket: Option<CallbackTicket>,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.task_channels
+            .submit_queued(config.schedule, self.queued(config, ticket, callback))
+    }
+
+    fn enqueue_reserved(
+        &self,
+        config: MainThreadRunConfiguration,
+        ticket: Option<CallbackTicket>,
+        permit: Option<OwnedSemaphorePermit>,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.task_channels.submit_reserved(
+            config.schedule,
+            self.queued(config, ticket, callback),
+            permit,
+        )
+    }
+
+    fn try_run_inline<Runnable, Output>(
+        &self,
+        config: &MainThreadRunConfiguration,
+        runnable: Runnable,
+    ) -> Result<Output, Runnable>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output,
+    {
+        let waits = !config.after.is_empty() || config.run_at_tick.is_some();
+        pump::try_run_inline(self.task_channels.resolve(config.schedule), waits, runnable)
+    }
+
+    fn queued(
+        &self,
+        config: MainThreadRunConfiguration,
+        ticket: Option<CallbackTicket>,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) -> QueuedCallback {
+        QueuedCallback {
+            task: self.task_id,
+            priority: config.priority,
+            ticket,
+            after: config.after,
+            run_at_tick: config.run_at_tick,
+            coalesce: config
+                .coalesce_key
+                .map(|key| self.task_channels.coalesce(key)),
+            ..QueuedCallback::new(Box::new(callback))
+        }
+    }
+
     /// Invokes a synchronous callback on the main Bevy thread. The callback will have mutable access to the
     /// main Bevy [`World`], allowing it to update any resources or entities that it wants. The callback can
     /// report results back to the background thread by returning an output value, which will be returned on
@@ -76,10 +349,57 @@ impl TaskContext {
         self.submit_on_main_thread_with_config(runnable, Default::default())
     }
 
+    /// Like [`submit_on_main_thread`](Self::submit_on_main_thread), but runs the callback in the given
+    /// schedule rather than the default one.
+    pub fn submit_on_main_thread_in<Runnable, Output>(
+        &self,
+        schedule: impl ScheduleLabel,
+        runnable: Runnable,
+    ) -> Receiver<Output>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        self.submit_on_main_thread_with_config(
+            runnable,
+            MainThreadRunConfiguration::new_with_schedule(schedule),
+        )
+    }
+
+    /// Like [`submit_on_main_thread`](Self::submit_on_main_thread), but sends every callback in
+    /// `batch` as a single queued callback which runs them in order under one exclusive access to
+    /// the world. Tasks which emit many small mutations should batch them to save the per-callback
+    /// queueing overhead. Callbacks of different types can be batched by boxing them.
+    pub fn submit_batch_on_main_thread<Runnable, Output>(
+        &self,
+        batch: impl IntoIterator<Item = Runnable>,
+    ) -> Receiver<Vec<Output>>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        let batch: Vec<Runnable> = batch.into_iter().collect();
+        self.submit_on_main_thread(move |mut ctx| {
+            batch
+                .into_iter()
+                .map(|runnable| runnable(ctx.reborrow()))
+                .collect()
+        })
+    }
+
     /// Invokes a synchronous callback on the main Bevy thread. The callback will have mutable access to the
     /// main Bevy [`World`], allowing it to update any resources or entities that it wants. The callback can
     /// report results back to the background thread by returning an output value, which will then be returned from
     /// this async function once the callback runs.
+    ///
+    /// Dropping the returned future before the callback runs, e.g. by aborting the task, cancels the
+    /// callback, so it never touches the world.
+    ///
+    /// If this is awaited by a main thread callback through
+    /// [`MainThreadContext::block_on`], the callback is run inline straight away rather than queued,
+    /// since the pump can't get to it until the blocked callback returns. Blocking on it from a
+    /// callback any other way, or when it's configured for another schedule, to run after other
+    /// callbacks or at a given tick, panics rather than deadlocking.
     pub async fn run_on_main_thread_with_config<Runnable, Output>(
         &self,
         runnable: Runnable,
@@ -89,16 +409,21 @@ impl TaskContext {
         Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
         Output: Send + 'static,
     {
+        let runnable = match self.try_run_inline(&config, runnable) {
+            Ok(output) => return output,
+            Err(runnable) => runnable,
+        };
         let (output_tx, output_rx) = tokio::sync::oneshot::channel();
-        if self.task_channels.submit(config.schedule,
-            move |ctx| {
-                if output_tx.send(runnable(ctx)).is_err() {
-                    panic!(
-                        "Failed to send output from operation run on main thread back to waiting task"
-                    );
+        if self
+            .enqueue(config, None, move |ctx| {
+                // The waiting future was dropped, so nothing wants the callback to run any more.
+                if output_tx.is_closed() {
+                    return;
                 }
-            }
-        ).is_err() {
+                let _ = output_tx.send(runnable(ctx));
+            })
+            .is_err()
+        {
             panic!("Failed to send operation to be run on main thread");
         }
         output_rx
@@ -110,6 +435,10 @@ impl TaskContext {
     /// main Bevy [`World`], allowing it to update any resources or entities that it wants. The callback can
     /// report results back to the background thread by returning an output value, which will then be returned from
     /// this async function once the callback runs.
+    ///
+    /// Panics if the callback can't be queued or is discarded before it runs. Long-lived tasks which
+    /// should exit cleanly while the app is closing should use
+    /// [`try_run_on_main_thread`](Self::try_run_on_main_thread) instead.
     pub async fn run_on_main_thread<Runnable, Output>(&self, runnable: Runnable) -> Output
     where
         Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
@@ -118,4 +447,457 @@ impl TaskContext {
         self.run_on_main_thread_with_config(runnable, Default::default())
             .await
     }
+
+    /// Like [`run_on_main_thread`](Self::run_on_main_thread), but gives up waiting once `timeout`
+    /// has passed. A callback which timed out is skipped if it's reached later.
+    pub async fn run_on_main_thread_with_timeout<Runnable, Output>(
+        &self,
+        timeout: impl Into<TimeoutAfter>,
+        runnable: Runnable,
+    ) -> Result<Output, Timeout>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        self.run_on_main_thread_with_timeout_and_config(timeout, runnable, Default::default())
+            .await
+    }
+
+    /// Like [`run_on_main_thread_with_config`](Self::run_on_main_thread_with_config), but gives up
+    /// waiting once `timeout` has passed, e.g. when submitting to a custom schedule which may no
+    /// longer be run. A callback which timed out is skipped if it's reached later.
+    pub async fn run_on_main_thread_with_timeout_and_config<Runnable, Output>(
+        &self,
+        timeout: impl Into<TimeoutAfter>,
+        runnable: Runnable,
+        config: MainThreadRunConfiguration,
+    ) -> Result<Output, Timeout>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        let runnable = match self.try_run_inline(&config, runnable) {
+            Ok(output) => return Ok(output),
+            Err(runnable) => runnable,
+        };
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        if self
+            .enqueue(config, None, move |ctx| {
+                // The receiver is dropped once the timeout passes.
+                if !output_tx.is_closed() {
+                    let _ = output_tx.send(runnable(ctx));
+                }
+            })
+            .is_err()
+        {
+            panic!("Failed to send operation to be run on main thread");
+        }
+        let expired = std::pin::pin!(self.expired(timeout.into()));
+        match futures_util::future::select(output_rx, expired).await {
+            Either::Left((output, _)) => {
+                Ok(output.expect("Failed to receive output from operation on main thread"))
+            }
+            Either::Right(_) => Err(Timeout),
+        }
+    }
+
+    /// Resolves once `timeout` has passed, checking once per update.
+    async fn expired(&self, timeout: TimeoutAfter) {
+        let _sleeper = TickSleeper::register(&self.tick_sleepers);
+        let mut tick_rx = self.tick_rx.clone();
+        let started = Instant::now();
+        let start_tick = self.ticks.load(Ordering::SeqCst);
+        loop {
+            tick_rx.borrow_and_update();
+            let expired = match timeout {
+                TimeoutAfter::Frames(frames) => {
+                    self.ticks.load(Ordering::SeqCst).wrapping_sub(start_tick) >= frames
+                }
+                TimeoutAfter::Duration(duration) => started.elapsed() >= duration,
+            };
+            if expired || tick_rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Like [`run_on_main_thread`](Self::run_on_main_thread), but returns an error rather than
+    /// panicking if the callback can't be run, including when the app exits before it gets to run.
+    pub async fn try_run_on_main_thread<Runnable, Output>(
+        &self,
+        runnable: Runnable,
+    ) -> Result<Output, MainThreadError>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        self.try_run_on_main_thread_with_config(runnable, Default::default())
+            .await
+    }
+
+    /// Like [`try_run_on_main_thread`](Self::try_run_on_main_thread), but with the given
+    /// configuration, like [`run_on_main_thread_with_config`](Self::run_on_main_thread_with_config).
+    pub async fn try_run_on_main_thread_with_config<Runnable, Output>(
+        &self,
+        runnable: Runnable,
+        config: MainThreadRunConfiguration,
+    ) -> Result<Output, MainThreadError>
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        if self.app_exit_rx.borrow().is_some() {
+            return Err(MainThreadError::AppExited);
+        }
+        let runnable = match self.try_run_inline(&config, runnable) {
+            Ok(output) => return Ok(output),
+            Err(runnable) => runnable,
+        };
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        self.enqueue(config, None, move |ctx| {
+            // Skip the callback if the waiting future was dropped.
+            if !output_tx.is_closed() {
+                let _ = output_tx.send(runnable(ctx));
+            }
+        })
+        .map_err(|_| MainThreadError::SubmitFailed)?;
+        // Callbacks still queued when the app exits never run, so stop waiting for them then. The
+        // output is checked first, in case the callback ran in the frame the app exited in.
+        let app_exit = std::pin::pin!(self.on_app_exit());
+        match futures_util::future::select(output_rx, app_exit).await {
+            Either::Left((output, _)) => output.map_err(|_| MainThreadError::Dropped),
+            Either::Right(_) => Err(MainThreadError::AppExited),
+        }
+    }
+
+    /// Like [`run_on_main_thread`](Self::run_on_main_thread), but the callback only gets shared
+    /// access to the [`World`]. It's run by a non-exclusive system, possibly on another thread, so it
+    /// can run in parallel with other systems which only read rather than pausing the schedule.
+    pub async fn run_on_main_thread_readonly<Runnable, Output>(&self, runnable: Runnable) -> Output
+    where
+        Runnable: FnOnce(&World) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        let schedule = MainThreadRunConfiguration::default().schedule;
+        if self
+            .task_channels
+            .submit_readonly(schedule, move |world| {
+                // Skip the callback if the waiting future was dropped.
+                if !output_tx.is_closed() {
+                    let _ = output_tx.send(runnable(world));
+                }
+            })
+            .is_err()
+        {
+            panic!("Failed to send operation to be run on main thread");
+        }
+        output_rx
+            .await
+            .expect("Failed to receive output from operation on main thread")
+    }
+
+    /// Runs a callback in two phases: `read` gets shared access to the [`World`] and runs in parallel
+    /// with other systems like [`run_on_main_thread_readonly`](Self::run_on_main_thread_readonly),
+    /// then `write` gets its output and exclusive access later in the same frame. Heavy extraction
+    /// work can go in `read` so that it doesn't hold up the whole schedule.
+    pub async fn run_on_main_thread_two_phase<Read, Write, Intermediate, Output>(
+        &self,
+        read: Read,
+        write: Write,
+    ) -> Output
+    where
+        Read: FnOnce(&World) -> Intermediate + Send + 'static,
+        Write: FnOnce(Intermediate, &mut World) -> Output + Send + 'static,
+        Intermediate: Send + 'static,
+        Output: Send + 'static,
+    {
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        let schedule = MainThreadRunConfiguration::default().schedule;
+        let task_channels = self.task_channels.clone();
+        let task = self.task_id;
+        if self
+            .task_channels
+            .submit_readonly(schedule, move |world| {
+                // Skip both phases if the waiting future was dropped.
+                if output_tx.is_closed() {
+                    return;
+                }
+                let intermediate = read(world);
+                let _ = task_channels.submit_from(schedule, task, move |ctx| {
+                    if !output_tx.is_closed() {
+                        let _ = output_tx.send(write(intermediate, ctx.world));
+                    }
+                });
+            })
+            .is_err()
+        {
+            panic!("Failed to send operation to be run on main thread");
+        }
+        output_rx
+            .await
+            .expect("Failed to receive output from operation on main thread")
+    }
+
+    /// Runs `f` with a reference to resource `R` on the main thread and returns its output, e.g.
+    /// `ctx.with_resource::<Score, _>(|score| score.0).await`. Panics on the main thread if `R`
+    /// doesn't exist.
+    pub async fn with_resource<R: Resource, Output: Send + 'static>(
+        &self,
+        f: impl FnOnce(&R) -> Output + Send + 'static,
+    ) -> Output {
+        self.run_on_main_thread(|mut ctx| ctx.run::<Res<R>, _, _>(|resource| f(&resource)))
+            .await
+    }
+
+    /// Like [`with_resource`](Self::with_resource), but with mutable access to the resource.
+    pub async fn with_resource_mut<R: Resource, Output: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut R) -> Output + Send + 'static,
+    ) -> Output {
+        self.run_on_main_thread(|mut ctx| {
+            ctx.run::<ResMut<R>, _, _>(|mut resource| f(&mut resource))
+        })
+        .await
+    }
+
+    /// Spawns an entity with `bundle` in the main world and returns its id, so the task can keep
+    /// working with it, e.g. through [`entity`](Self::entity).
+    pub async fn spawn(&self, bundle: impl Bundle) -> Entity {
+        self.run_on_main_thread(move |ctx| ctx.world.spawn(bundle).id())
+            .await
+    }
+
+    /// Returns a buffer which records changes to the world, such as spawning and despawning
+    /// entities, to be applied together with [`TaskCommands::flush`].
+    pub fn commands(&self) -> TaskCommands<'_> {
+        TaskCommands::new(self)
+    }
+
+    /// Starts a batch of changes to `entity`, e.g.
+    /// `ctx.entity(e).insert(Loaded).remove::<Loading>().await`, which are made in a single main
+    /// thread callback.
+    pub fn entity(&self, entity: Entity) -> TaskEntityCommands<'_> {
+        TaskEntityCommands::new(self, entity)
+    }
+
+    /// Inserts `resource` into the main world, replacing any existing value, and waits for it to be
+    /// inserted.
+    pub async fn insert_resource<R: Resource>(&self, resource: R) {
+        self.run_on_main_thread(move |ctx| ctx.world.insert_resource(resource))
+            .await
+    }
+
+    /// Removes resource `R` from the main world, returning its value if it existed.
+    pub async fn remove_resource<R: Resource>(&self) -> Option<R> {
+        self.run_on_main_thread(|ctx| ctx.world.remove_resource::<R>())
+            .await
+    }
+
+    /// Like [`run_on_main_thread`](Self::run_on_main_thread), but runs the callback in the given
+    /// schedule rather than the default one.
+    pub async fn run_on_main_thread_in<Runnable, Output>(
+        &self,
+        schedule: impl ScheduleLabel,
+        runnable: Runnable,
+    ) -> Output
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        self.run_on_main_thread_with_config(
+            runnable,
+            MainThreadRunConfiguration::new_with_schedule(schedule),
+        )
+        .await
+    }
+
+    /// Runs a callback on the main thread once the update tick count reaches `tick`, e.g. to apply a
+    /// replicated input at simulation tick N, and returns its output. The callback is held in its
+    /// queue by the pump until then.
+    pub async fn run_at_tick<Runnable, Output>(&self, tick: usize, runnable: Runnable) -> Output
+    where
+        Runnable: FnOnce(MainThreadContext) -> Output + Send + 'static,
+        Output: Send + 'static,
+    {
+        self.run_on_main_thread_with_config(
+            runnable,
+            MainThreadRunConfiguration::default().at_tick(tick),
+        )
+        .await
+    }
+
+    /// Queues an event to be written into the main world's [`Events`](bevy_ecs::event::Events) the next
+    /// time callbacks are run, without waiting for it to be sent.
+    pub fn send_event<E: Event>(&self, event: E) {
+        self.submit_on_main_thread(move |ctx| {
+            ctx.world.send_event(event);
+        });
+    }
+
+    /// Waits for the next event of type `E` to be sent in the main world, e.g. to react to a
+    /// `LevelLoaded` event without polling. Only events sent after this is called are seen. `E` must
+    /// have been registered with `App::add_event`.
+    pub async fn next_event<E: Event + Clone>(&self) -> E {
+        pump::assert_not_in_callback();
+        self.run_on_main_thread(EventListeners::<E>::next)
+            .await
+            .await
+            .expect("Event listener was dropped before an event was received")
+    }
+
+    /// Returns a stream of every event of type `E` sent in the main world from now on, for long-lived
+    /// tasks which consume events, e.g. a network task sending `OutgoingPacket` events. Buffers up
+    /// to [`DEFAULT_EVENT_STREAM_CAPACITY`] events. `E` must have been registered with
+    /// `App::add_event`.
+    pub fn events<E: Event + Clone>(&self) -> EventStream<E> {
+        self.events_with_capacity(DEFAULT_EVENT_STREAM_CAPACITY)
+    }
+
+    /// Like [`events`](Self::events), but buffers up to `capacity` events before dropping them.
+    pub fn events_with_capacity<E: Event + Clone>(&self, capacity: usize) -> EventStream<E> {
+        let (stream, event_tx, lagged) = EventStream::new(capacity);
+        self.submit_on_main_thread(move |ctx| EventListeners::<E>::stream(ctx, event_tx, lagged));
+        stream
+    }
+
+    /// Clones several resources out of the main world in one round trip, e.g.
+    /// `let (settings, score) = ctx.extract::<(Res<Settings>, Res<Score>)>().await`.
+    pub async fn extract<E: Extract + 'static>(&self) -> E::Output {
+        self.run_on_main_thread(|ctx| E::extract(ctx.world)).await
+    }
+
+    /// Runs a query on the main thread and returns an owned copy of every row in one round trip, e.g.
+    /// `ctx.query::<(Entity, &Transform)>().await` for a snapshot of where everything is.
+    pub async fn query<Q: OwnedQueryData + 'static>(&self) -> Vec<Q::Owned> {
+        self.query_filtered::<Q, ()>().await
+    }
+
+    /// Like [`query`](Self::query), but only returns the rows matching the filter `F`.
+    pub async fn query_filtered<Q: OwnedQueryData + 'static, F: QueryFilter + 'static>(
+        &self,
+    ) -> Vec<Q::Owned> {
+        self.run_on_main_thread(|ctx| {
+            let mut query = ctx.world.query_filtered::<Q, F>();
+            query.iter(ctx.world).map(Q::into_owned).collect()
+        })
+        .await
+    }
+
+    /// Waits until `condition` returns `true`. The condition is evaluated on the main thread right
+    /// away and then once per frame, so tasks can await arbitrary game state, e.g.
+    /// `ctx.wait_until(|world| world.resource::<Score>().0 >= 100).await`.
+    pub async fn wait_until<Condition>(&self, mut condition: Condition)
+    where
+        Condition: FnMut(&World) -> bool + Send + 'static,
+    {
+        self.wait_for_world(move |world| condition(world).then_some(()))
+            .await
+    }
+
+    /// Waits until resource `R` is next changed, according to Bevy's change detection, and returns
+    /// a clone of its new value. Changes made before this is called aren't seen.
+    pub async fn wait_for_resource_changed<R: Resource + Clone>(&self) -> R {
+        let mut since = None;
+        self.wait_for_world(move |world| {
+            let this_run = world.change_tick();
+            let since = *since.get_or_insert(this_run);
+            let ticks = world.get_resource_change_ticks::<R>()?;
+            ticks
+                .is_changed(since, this_run)
+                .then(|| world.resource::<R>().clone())
+        })
+        .await
+    }
+
+    /// Waits until `entity` has a `C` component and returns a clone of it, or an error if the entity
+    /// is despawned first.
+    pub async fn wait_for_component<C: Component + Clone>(
+        &self,
+        entity: Entity,
+    ) -> Result<C, EntityDespawned> {
+        self.wait_for_world(move |world| match world.get_entity(entity) {
+            Some(entity) => entity.get::<C>().cloned().map(Ok),
+            None => Some(Err(EntityDespawned(entity))),
+        })
+        .await
+    }
+
+    /// Evaluates `check` on the main thread right away and then once per frame, until it returns a
+    /// value.
+    ///
+    /// If this is awaited by a main thread callback through [`MainThreadContext::block_on`], `check`
+    /// is evaluated inline, and panics if it doesn't return a value straight away since later
+    /// frames can't run until the blocked callback returns.
+    async fn wait_for_world<Check, Output>(&self, mut check: Check) -> Output
+    where
+        Check: FnMut(&mut World) -> Option<Output> + Send + 'static,
+        Output: Send + 'static,
+    {
+        match self.try_run_inline(&Default::default(), |ctx| check(ctx.world)) {
+            Ok(Some(output)) => return output,
+            Ok(None) => pump::assert_not_in_callback(),
+            Err(_) => {}
+        }
+        let (output_tx, output_rx) = tokio::sync::oneshot::channel();
+        let mut output_tx = Some(output_tx);
+        self.submit_on_main_thread(move |ctx| {
+            WorldWaiters::add(ctx.world, move |world| {
+                let Some(tx) = output_tx.take() else {
+                    return true;
+                };
+                // The task stopped waiting, e.g. because it was aborted.
+                if tx.is_closed() {
+                    return true;
+                }
+                if let Some(output) = check(world) {
+                    let _ = tx.send(output);
+                    return true;
+                }
+                output_tx = Some(tx);
+                false
+            });
+        });
+        output_rx
+            .await
+            .expect("Main thread dropped a condition before it was met")
+    }
+
+    /// Queues an observer trigger for `event` to be fired on the main world the next time callbacks
+    /// are run, so observers can react to async completions without polling.
+    pub fn trigger<E: Event>(&self, event: E) {
+        self.submit_on_main_thread(move |ctx| {
+            ctx.world.trigger(event);
+        });
+    }
+
+    /// Like [`trigger`](Self::trigger), but targets the observers watching the given entities or
+    /// components.
+    pub fn trigger_targets<E: Event>(
+        &self,
+        event: E,
+        targets: impl TriggerTargets + Send + Sync + 'static,
+    ) {
+        self.submit_on_main_thread(move |ctx| {
+            ctx.world.trigger_targets(event, targets);
+        });
+    }
+
+    /// Requests that the app exits with the given code by sending an [`AppExit`] event on the main
+    /// thread. A code of zero is treated as a successful exit.
+    pub fn exit_app(&self, code: u8) {
+        self.submit_on_main_thread(move |ctx| {
+            ctx.world.send_event(AppExit::from_code(code));
+        });
+    }
+
+    /// Waits until the app is exiting, returning the [`AppExit`] event which caused it. If the app is
+    /// torn down without an [`AppExit`] event being observed, this resolves with [`AppExit::Success`].
+    pub async fn on_app_exit(&self) -> AppExit {
+        let mut exit_rx = self.app_exit_rx.clone();
+        match exit_rx.wait_for(Option::is_some).await {
+            Ok(exit) => exit.clone().unwrap_or(AppExit::Success),
+            Err(_) => AppExit::Success,
+        }
+    }
 }
@@ -0,0 +1,100 @@
+use crate::{
+    task_channels::TaskChannels, ticks::UpdateTicks, AbortHandle, TaskRegistry, TaskTimings,
+    TrackedTasks,
+};
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::system::{Res, ResMut, Resource};
+use bevy_egui::{egui, EguiContexts};
+
+/// Renders an egui window listing every running task with its name and age in frames, with a button
+/// to abort each, along with the number of main thread callbacks queued per schedule. Requires the
+/// [`TasksPlugin`](crate::TasksPlugin) and bevy_egui's `EguiPlugin` to be added.
+#[derive(Default)]
+pub struct TasksDebugOverlayPlugin;
+
+/// Controls whether the [`TasksDebugOverlayPlugin`] window is shown.
+#[derive(Resource)]
+pub struct TasksDebugOverlay {
+    pub visible: bool,
+}
+
+impl Default for TasksDebugOverlay {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+impl TasksDebugOverlayPlugin {
+    fn show_overlay(
+        mut contexts: EguiContexts,
+        mut overlay: ResMut<TasksDebugOverlay>,
+        tracked: Res<TrackedTasks>,
+        registry: Res<TaskRegistry>,
+        ticks: Res<UpdateTicks>,
+        task_channels: Res<TaskChannels>,
+        timings: Option<Res<TaskTimings>>,
+    ) {
+        if !overlay.visible {
+            return;
+        }
+        egui::Window::new("Tasks")
+            .open(&mut overlay.visible)
+            .show(contexts.ctx_mut(), |ui| {
+                let tasks = tracked.snapshot();
+                ui.heading(format!("Running tasks ({})", tasks.len()));
+                egui::Grid::new("tasks").striped(true).show(ui, |ui| {
+                    ui.label("Task");
+                    ui.label("Name");
+                    ui.label("Age (frames)");
+                    ui.label("Runtime");
+                    ui.label("Busy");
+                    ui.label("");
+                    ui.end_row();
+                    for (id, handle) in tasks {
+                        let info = registry.get(id);
+                        ui.label(id.to_string());
+                        ui.label(
+                            info.as_ref()
+                                .and_then(|info| info.name.as_deref())
+                                .unwrap_or(""),
+                        );
+                        let age = info
+                            .map(|info| ticks.tick().wrapping_sub(info.spawn_tick).to_string())
+                            .unwrap_or_default();
+                        ui.label(age);
+                        ui.label(match handle {
+                            AbortHandle::Tokio(_) => "tokio",
+                            AbortHandle::Futures(_) => "futures",
+                        });
+                        let busy = timings
+                            .as_ref()
+                            .and_then(|timings| timings.busy_time(id))
+                            .map(|busy| format!("{busy:.1?}"))
+                            .unwrap_or_default();
+                        ui.label(busy);
+                        if ui.button("Abort").clicked() {
+                            handle.abort();
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.separator();
+                ui.heading("Queued callbacks");
+                egui::Grid::new("queues").striped(true).show(ui, |ui| {
+                    for (schedule, len) in task_channels.queue_lengths() {
+                        ui.label(format!("{schedule:?}"));
+                        ui.label(len.to_string());
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+}
+
+impl Plugin for TasksDebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TasksDebugOverlay>()
+            .add_systems(Update, Self::show_overlay);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{task_channels::TaskChannels, tracking::TrackedTasks, TaskId, TaskTimings};
+use bevy_app::{App, Plugin, Update};
+use bevy_diagnostic::{
+    Diagnostic, DiagnosticMeasurement, DiagnosticPath, DiagnosticsStore, RegisterDiagnostic,
+};
+use bevy_ecs::system::{Local, Res, ResMut};
+use bevy_time::Time;
+use bevy_utils::{Duration, Instant};
+
+/// Registers diagnostics for background tasks, so they show up alongside FPS in the output of
+/// Bevy's `LogDiagnosticsPlugin`:
+///
+/// - [`LIVE_TASKS`](Self::LIVE_TASKS): tasks spawned through [`Tasks`](crate::Tasks) which are still
+///   running.
+/// - [`TASKS_SPAWNED_PER_SECOND`](Self::TASKS_SPAWNED_PER_SECOND)
+/// - [`TASK_BUSY_TIME`](Self::TASK_BUSY_TIME): milliseconds spent polling tasks since the last
+///   frame, summed over every task. Only measured when the `task-timing` feature is enabled.
+/// - `tasks/queue/<schedule>`: main thread callbacks waiting to run in each schedule, registered the
+///   first time a schedule is seen.
+/// - `tasks/queue_age/<schedule>`: how long, in seconds, the oldest callback in each schedule's
+///   queue has been waiting.
+pub struct TasksDiagnosticsPlugin;
+
+impl TasksDiagnosticsPlugin {
+    pub const LIVE_TASKS: DiagnosticPath = DiagnosticPath::const_new("tasks/live");
+    pub const TASKS_SPAWNED_PER_SECOND: DiagnosticPath =
+        DiagnosticPath::const_new("tasks/spawned_per_second");
+    pub const TASK_BUSY_TIME: DiagnosticPath = DiagnosticPath::const_new("tasks/busy_time");
+
+    fn diagnostic_system(
+        mut store: ResMut<DiagnosticsStore>,
+        tracked: Res<TrackedTasks>,
+        task_channels: Res<TaskChannels>,
+        timings: Option<Res<TaskTimings>>,
+        time: Res<Time>,
+        mut last_allocated: Local<u64>,
+        mut last_busy_time: Local<Duration>,
+    ) {
+        let now = Instant::now();
+        // Schedule queues appear as tasks first submit to them, so their diagnostics are added
+        // directly to the store rather than registered up front.
+        let mut measure = |path: &DiagnosticPath, value: f64| {
+            if store.get(path).is_none() {
+                store.add(Diagnostic::new(path.clone()));
+            }
+            if let Some(diagnostic) = store.get_mut(path).filter(|d| d.is_enabled) {
+                diagnostic.add_measurement(DiagnosticMeasurement { time: now, value });
+            }
+        };
+
+        measure(&Self::LIVE_TASKS, tracked.len() as f64);
+
+        let allocated = TaskId::allocated();
+        let spawned = allocated - *last_allocated;
+        *last_allocated = allocated;
+        let delta = time.delta_seconds_f64();
+        if delta > 0.0 {
+            measure(&Self::TASKS_SPAWNED_PER_SECOND, spawned as f64 / delta);
+        }
+
+        if let Some(timings) = timings {
+            let busy_time = timings.total_busy_time();
+            let busy = busy_time.saturating_sub(*last_busy_time);
+            *last_busy_time = busy_time;
+            measure(&Self::TASK_BUSY_TIME, busy.as_secs_f64() * 1000.0);
+        }
+
+        for (schedule, len) in task_channels.queue_lengths() {
+            let path = DiagnosticPath::new(format!("tasks/queue/{schedule:?}"));
+            measure(&path, len as f64);
+            let age = task_channels
+                .oldest_enqueued(schedule)
+                .map_or(0.0, |queued_at| {
+                    now.saturating_duration_since(queued_at).as_secs_f64()
+                });
+            let path = DiagnosticPath::new(format!("tasks/queue_age/{schedule:?}"));
+            measure(&path, age);
+        }
+    }
+}
+
+impl Plugin for TasksDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::LIVE_TASKS))
+            .register_diagnostic(Diagnostic::new(Self::TASKS_SPAWNED_PER_SECOND))
+            .register_diagnostic(Diagnostic::new(Self::TASK_BUSY_TIME).with_suffix("ms"))
+            .add_systems(Update, Self::diagnostic_system);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{EntityDespawned, TaskContext};
+use bevy_ecs::{bundle::Bundle, entity::Entity, world::World};
+use std::{
+    future::{Future, IntoFuture},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::oneshot::Receiver;
+
+type EntityCommand = Box<dyn FnOnce(&mut World, Entity) + Send + 'static>;
+
+/// A batch of changes to an entity made from a task, created by
+/// [`TaskContext::entity`]. Nothing happens until the batch is either awaited, which waits for the
+/// changes to be made, or [`apply`](Self::apply)'d. Either way the whole batch runs in a single main
+/// thread callback.
+#[must_use = "entity commands do nothing unless awaited or applied"]
+pub struct TaskEntityCommands<'a> {
+    ctx: &'a TaskContext,
+    entity: Entity,
+    commands: Vec<EntityCommand>,
+}
+
+impl<'a> TaskEntityCommands<'a> {
+    pub(crate) fn new(ctx: &'a TaskContext, entity: Entity) -> Self {
+        Self {
+            ctx,
+            entity,
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+
+    pub fn insert(self, bundle: impl Bundle) -> Self {
+        self.push(move |world, entity| {
+            world.entity_mut(entity).insert(bundle);
+        })
+    }
+
+    pub fn remove<B: Bundle>(self) -> Self {
+        self.push(|world, entity| {
+            world.entity_mut(entity).remove::<B>();
+        })
+    }
+
+    /// Despawns the entity. Any commands queued after this are skipped.
+    pub fn despawn(self) -> Self {
+        self.push(|world, entity| {
+            world.despawn(entity);
+        })
+    }
+
+    /// Queues the batch without waiting for it to run.
+    pub fn apply(self) {
+        let _ = self.submit();
+    }
+
+    fn push(mut self, command: impl FnOnce(&mut World, Entity) + Send + 'static) -> Self {
+        self.commands.push(Box::new(command));
+        self
+    }
+
+    fn submit(self) -> Receiver<Result<(), EntityDespawned>> {
+        let Self {
+            ctx,
+            entity,
+            commands,
+        } = self;
+        ctx.submit_on_main_thread(move |ctx| {
+            for command in commands {
+                if ctx.world.get_entity(entity).is_none() {
+                    return Err(EntityDespawned(entity));
+                }
+                command(ctx.world, entity);
+            }
+            Ok(())
+        })
+    }
+}
+
+impl IntoFuture for TaskEntityCommands<'_> {
+    /// An error if the entity didn't exist when the batch ran, or was despawned partway through.
+    /// Commands before that point are still applied.
+    type Output = Result<(), EntityDespawned>;
+    type IntoFuture = AppliedEntityCommands;
+
+    fn into_future(self) -> Self::IntoFuture {
+        AppliedEntityCommands {
+            result_rx: self.submit(),
+        }
+    }
+}
+
+/// The future returned by awaiting [`TaskEntityCommands`].
+pub struct AppliedEntityCommands {
+    result_rx: Receiver<Result<(), EntityDespawned>>,
+}
+
+impl Future for AppliedEntityCommands {
+    type Output = Result<(), EntityDespawned>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.result_rx)
+            .poll(cx)
+            .map(|result| result.expect("Failed to receive output from operation on main thread"))
+    }
+}
@@ -0,0 +1,162 @@
+use crate::context::main_thread::MainThreadContext;
+use bevy_ecs::{
+    event::{Event, Events, ManualEventReader},
+    system::Resource,
+    world::{Mut, World},
+};
+use bevy_utils::tracing::warn;
+use futures_util::Stream;
+use std::{
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// How many events an [`EventStream`] buffers by default before it starts dropping them.
+pub const DEFAULT_EVENT_STREAM_CAPACITY: usize = 64;
+
+/// Forwards Bevy events of every type a task is listening for out to those tasks. Each event type
+/// installs its forwarder the first time a task listens for it.
+#[derive(Resource, Default)]
+pub(crate) struct EventForwarders {
+    forwarders: Vec<fn(&mut World)>,
+}
+
+impl EventForwarders {
+    pub(crate) fn run_system(world: &mut World) {
+        let forwarders = world.resource::<Self>().forwarders.clone();
+        for forward in forwarders {
+            forward(world);
+        }
+    }
+}
+
+/// The tasks listening for events of type `E`.
+#[derive(Resource)]
+pub(crate) struct EventListeners<E: Event> {
+    reader: ManualEventReader<E>,
+    next: Vec<oneshot::Sender<E>>,
+    streams: Vec<EventStreamSender<E>>,
+}
+
+struct EventStreamSender<E> {
+    event_tx: mpsc::Sender<E>,
+    lagged: Arc<AtomicU64>,
+}
+
+impl<E: Event + Clone> EventListeners<E> {
+    /// Returns the listeners for `E`, installing its forwarder if this is the first listener. Only
+    /// events sent after this call are forwarded.
+    pub(crate) fn install(world: &mut World) -> Mut<Self> {
+        if !world.contains_resource::<Self>() {
+            let reader = world
+                .get_resource::<Events<E>>()
+                .map(Events::get_reader_current)
+                .unwrap_or_default();
+            world.insert_resource(Self {
+                reader,
+                next: Vec::new(),
+                streams: Vec::new(),
+            });
+            world
+                .get_resource_or_insert_with(EventForwarders::default)
+                .forwarders
+                .push(Self::forward);
+        }
+        world.resource_mut::<Self>()
+    }
+
+    /// Registers a listener which is sent the next event of type `E`.
+    pub(crate) fn next(ctx: MainThreadContext) -> oneshot::Receiver<E> {
+        let (event_tx, event_rx) = oneshot::channel();
+        Self::install(ctx.world).next.push(event_tx);
+        event_rx
+    }
+
+    /// Registers a stream which is sent every event of type `E`.
+    pub(crate) fn stream(
+        ctx: MainThreadContext,
+        event_tx: mpsc::Sender<E>,
+        lagged: Arc<AtomicU64>,
+    ) {
+        Self::install(ctx.world)
+            .streams
+            .push(EventStreamSender { event_tx, lagged });
+    }
+
+    fn forward(world: &mut World) {
+        world.resource_scope(|world, mut listeners: Mut<Self>| {
+            let Some(events) = world.get_resource::<Events<E>>() else {
+                return;
+            };
+            let listeners = &mut *listeners;
+            for event in listeners.reader.read(events) {
+                for event_tx in listeners.next.drain(..) {
+                    let _ = event_tx.send(event.clone());
+                }
+                listeners
+                    .streams
+                    .retain(|stream| match stream.event_tx.try_send(event.clone()) {
+                        Ok(()) => true,
+                        Err(mpsc::error::TrySendError::Full(_)) => {
+                            stream.lagged.fetch_add(1, Ordering::Relaxed);
+                            true
+                        }
+                        Err(mpsc::error::TrySendError::Closed(_)) => false,
+                    });
+            }
+        });
+    }
+}
+
+/// A stream of every event of type `E` sent in the main world, created by
+/// [`TaskContext::events`](crate::TaskContext::events). Events are buffered up to a fixed capacity;
+/// if the task falls further behind than that, new events are dropped and counted in
+/// [`lagged`](Self::lagged).
+pub struct EventStream<E> {
+    event_rx: mpsc::Receiver<E>,
+    lagged: Arc<AtomicU64>,
+    reported_lag: u64,
+}
+
+impl<E: Event + Clone> EventStream<E> {
+    pub(crate) fn new(capacity: usize) -> (Self, mpsc::Sender<E>, Arc<AtomicU64>) {
+        let (event_tx, event_rx) = mpsc::channel(capacity);
+        let lagged = Arc::new(AtomicU64::new(0));
+        let stream = Self {
+            event_rx,
+            lagged: lagged.clone(),
+            reported_lag: 0,
+        };
+        (stream, event_tx, lagged)
+    }
+}
+
+impl<E> EventStream<E> {
+    /// How many events have been dropped in total because the stream's buffer was full.
+    pub fn lagged(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
+}
+
+impl<E> Stream for EventStream<E> {
+    type Item = E;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<E>> {
+        let this = self.get_mut();
+        let lagged = this.lagged.load(Ordering::Relaxed);
+        if lagged > this.reported_lag {
+            warn!(
+                "Event stream of {} fell behind and dropped {} events",
+                std::any::type_name::<E>(),
+                lagged - this.reported_lag
+            );
+            this.reported_lag = lagged;
+        }
+        this.event_rx.poll_recv(cx)
+    }
+}
@@ -0,0 +1,51 @@
+use bevy_ecs::{
+    system::{Res, Resource},
+    world::World,
+};
+
+/// Resources which can be cloned out of the world together by
+/// [`TaskContext::extract`](crate::TaskContext::extract). Implemented for `Res<R>` and
+/// `Option<Res<R>>` for cloneable resources, and tuples of those.
+pub trait Extract {
+    type Output: Send + 'static;
+
+    fn extract(world: &World) -> Self::Output;
+}
+
+impl<R: Resource + Clone> Extract for Res<'_, R> {
+    type Output = R;
+
+    /// Panics if the resource doesn't exist, like `Res` in a system.
+    fn extract(world: &World) -> R {
+        world.resource::<R>().clone()
+    }
+}
+
+impl<R: Resource + Clone> Extract for Option<Res<'_, R>> {
+    type Output = Option<R>;
+
+    fn extract(world: &World) -> Option<R> {
+        world.get_resource::<R>().cloned()
+    }
+}
+
+macro_rules! impl_extract_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: Extract),*> Extract for ($($name,)*) {
+            type Output = ($($name::Output,)*);
+
+            fn extract(world: &World) -> Self::Output {
+                ($($name::extract(world),)*)
+            }
+        }
+    };
+}
+
+impl_extract_tuple!(A);
+impl_extract_tuple!(A, B);
+impl_extract_tuple!(A, B, C);
+impl_extract_tuple!(A, B, C, D);
+impl_extract_tuple!(A, B, C, D, E);
+impl_extract_tuple!(A, B, C, D, E, F);
+impl_extract_tuple!(A, B, C, D, E, F, G);
+impl_extract_tuple!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,185 @@
+use crate::{TaskContext, Tasks};
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::event::{Event, EventReader};
+use rfd::{AsyncFileDialog, FileHandle};
+use std::path::PathBuf;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FileDialogKind {
+    #[default]
+    PickFile,
+    PickFiles,
+    SaveFile,
+}
+
+/// Asks for a native (or browser) file dialog to be shown. Once the user has made a choice a
+/// [`FileDialogResult`] event is sent containing this request and the chosen files.
+#[derive(Event, Clone, Debug, Default)]
+pub struct FileDialogRequest {
+    pub kind: FileDialogKind,
+    pub title: Option<String>,
+    /// Pairs of filter names and the file extensions they allow.
+    pub filters: Vec<(String, Vec<String>)>,
+    pub file_name: Option<String>,
+    /// Whether picked files should be read into [`PickedFile::bytes`]. On wasm, reading the contents
+    /// is the only way to access a picked file.
+    pub read_contents: bool,
+}
+
+impl FileDialogRequest {
+    pub fn pick_file() -> Self {
+        Self::default()
+    }
+
+    pub fn pick_files() -> Self {
+        Self {
+            kind: FileDialogKind::PickFiles,
+            ..Default::default()
+        }
+    }
+
+    pub fn save_file() -> Self {
+        Self {
+            kind: FileDialogKind::SaveFile,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_filter(mut self, name: impl Into<String>, extensions: &[&str]) -> Self {
+        self.filters.push((
+            name.into(),
+            extensions.iter().map(|ext| ext.to_string()).collect(),
+        ));
+        self
+    }
+
+    pub fn with_file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    pub fn reading_contents(mut self) -> Self {
+        self.read_contents = true;
+        self
+    }
+
+    fn dialog(&self) -> AsyncFileDialog {
+        let mut dialog = AsyncFileDialog::new();
+        if let Some(title) = &self.title {
+            dialog = dialog.set_title(title);
+        }
+        for (name, extensions) in &self.filters {
+            dialog = dialog.add_filter(name, extensions);
+        }
+        if let Some(file_name) = &self.file_name {
+            dialog = dialog.set_file_name(file_name);
+        }
+        dialog
+    }
+}
+
+/// A file chosen in a file dialog.
+#[derive(Clone, Debug)]
+pub struct PickedFile {
+    pub name: String,
+    /// The file's path. Always `None` on wasm, where the browser doesn't expose paths.
+    pub path: Option<PathBuf>,
+    /// The file's contents, if [`FileDialogRequest::read_contents`] was set.
+    pub bytes: Option<Vec<u8>>,
+}
+
+impl PickedFile {
+    async fn from_handle(handle: FileHandle, read_contents: bool) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        let path = Some(handle.path().to_path_buf());
+        #[cfg(target_arch = "wasm32")]
+        let path = None;
+        let bytes = if read_contents {
+            Some(handle.read().await)
+        } else {
+            None
+        };
+        Self {
+            name: handle.file_name(),
+            path,
+            bytes,
+        }
+    }
+}
+
+/// Sent once the dialog shown for a [`FileDialogRequest`] has been closed. `files` is empty if the
+/// user cancelled the dialog.
+#[derive(Event, Clone, Debug)]
+pub struct FileDialogResult {
+    pub request: FileDialogRequest,
+    pub files: Vec<PickedFile>,
+}
+
+impl TaskContext {
+    /// Shows the file dialog described by `request` and waits for the user to choose. Returns an
+    /// empty list if the dialog was cancelled.
+    pub async fn file_dialog(&self, request: &FileDialogRequest) -> Vec<PickedFile> {
+        let dialog = request.dialog();
+        let handles = match request.kind {
+            FileDialogKind::PickFile => dialog.pick_file().await.into_iter().collect(),
+            FileDialogKind::PickFiles => dialog.pick_files().await.unwrap_or_default(),
+            FileDialogKind::SaveFile => dialog.save_file().await.into_iter().collect(),
+        };
+        let mut files = Vec::with_capacity(handles.len());
+        for handle in handles {
+            files.push(PickedFile::from_handle(handle, request.read_contents).await);
+        }
+        files
+    }
+
+    /// Shows an open-file dialog and waits for the user to pick a single file.
+    pub async fn pick_file(&self) -> Option<PickedFile> {
+        self.file_dialog(&FileDialogRequest::pick_file())
+            .await
+            .pop()
+    }
+
+    /// Shows a save-file dialog and waits for the user to choose a destination.
+    pub async fn save_file(&self) -> Option<PickedFile> {
+        self.file_dialog(&FileDialogRequest::save_file())
+            .await
+            .pop()
+    }
+}
+
+/// Shows a file dialog for each [`FileDialogRequest`] event and sends the outcome as a
+/// [`FileDialogResult`] event. Requires the [`TasksPlugin`](crate::TasksPlugin) to be added first,
+/// and on wasm the `wasm` feature.
+pub struct FileDialogPlugin;
+
+impl FileDialogPlugin {
+    fn show_requested_dialogs(mut requests: EventReader<FileDialogRequest>, tasks: Tasks) {
+        for request in requests.read().cloned() {
+            let task = |ctx: TaskContext| async move {
+                let files = ctx.file_dialog(&request).await;
+                ctx.submit_on_main_thread(move |ctx| {
+                    ctx.world.send_event(FileDialogResult { request, files });
+                });
+            };
+            // Browser file dialogs can't be moved between threads, so they have to run on the
+            // local executor rather than wherever `spawn_auto` would place them.
+            #[cfg(target_arch = "wasm32")]
+            tasks.spawn_wasm(task);
+            #[cfg(not(target_arch = "wasm32"))]
+            tasks.spawn_auto(task);
+        }
+    }
+}
+
+impl Plugin for FileDialogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FileDialogRequest>()
+            .add_event::<FileDialogResult>()
+            .add_systems(Update, Self::show_requested_dialogs);
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{TaskContext, Tasks};
+use bevy_app::{App, Plugin, Startup, Update};
+use bevy_ecs::{
+    event::{Event, EventReader},
+    system::{Res, Resource},
+};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+use tonic::{
+    transport::{Channel, Endpoint},
+    Status,
+};
+
+/// The connected tonic channel, inserted once [`GrpcPlugin`] has connected to its endpoint. The
+/// channel is cheap to clone and can be handed to generated tonic clients from any task.
+#[derive(Resource, Clone)]
+pub struct GrpcChannel(pub Channel);
+
+/// Lifecycle events for the connection made by [`GrpcPlugin`].
+#[derive(Event, Clone, Debug)]
+pub enum GrpcConnectionEvent {
+    Connected { endpoint: String },
+    Failed { endpoint: String, error: String },
+}
+
+/// A typed request to be sent over the [`GrpcChannel`] by the call registered with
+/// [`AddGrpcCall::add_grpc_call`]. The response arrives as a [`GrpcResponse`] with the same `id`.
+#[derive(Event, Clone, Debug)]
+pub struct GrpcRequest<Req> {
+    pub id: u64,
+    pub message: Req,
+}
+
+impl<Req> GrpcRequest<Req> {
+    pub fn new(message: Req) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            message,
+        }
+    }
+}
+
+/// The outcome of a [`GrpcRequest`].
+#[derive(Event, Clone, Debug)]
+pub struct GrpcResponse<Resp> {
+    pub id: u64,
+    pub result: Result<Resp, Status>,
+}
+
+impl TaskContext {
+    /// Connects a tonic channel to the given endpoint on the current runtime.
+    pub async fn grpc_connect(
+        &self,
+        endpoint: Endpoint,
+    ) -> Result<Channel, tonic::transport::Error> {
+        endpoint.connect().await
+    }
+}
+
+/// Connects to a gRPC endpoint at startup on the crate's runtime, inserting the [`GrpcChannel`]
+/// resource and sending a [`GrpcConnectionEvent`] once the attempt finishes. Requires the
+/// [`TasksPlugin`](crate::TasksPlugin) to be added first.
+pub struct GrpcPlugin {
+    pub endpoint: String,
+}
+
+impl GrpcPlugin {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+        }
+    }
+
+    fn connect(tasks: &Tasks, endpoint: String) {
+        tasks.spawn_tokio(move |ctx| async move {
+            let connected = match Endpoint::from_shared(endpoint.clone()) {
+                Ok(target) => ctx
+                    .grpc_connect(target)
+                    .await
+                    .map_err(|err| err.to_string()),
+                Err(err) => Err(err.to_string()),
+            };
+            ctx.submit_on_main_thread(move |ctx| match connected {
+                Ok(channel) => {
+                    ctx.world.insert_resource(GrpcChannel(channel));
+                    ctx.world
+                        .send_event(GrpcConnectionEvent::Connected { endpoint });
+                }
+                Err(error) => {
+                    ctx.world
+                        .send_event(GrpcConnectionEvent::Failed { endpoint, error });
+                }
+            });
+        });
+    }
+}
+
+impl Plugin for GrpcPlugin {
+    fn build(&self, app: &mut App) {
+        let endpoint = self.endpoint.clone();
+        app.add_event::<GrpcConnectionEvent>()
+            .add_systems(Startup, move |tasks: Tasks| {
+                Self::connect(&tasks, endpoint.clone())
+            });
+    }
+}
+
+/// Registers typed gRPC calls which are driven by [`GrpcRequest`] and [`GrpcResponse`] events.
+pub trait AddGrpcCall {
+    /// Sends every [`GrpcRequest<Req>`] event through `call`, which is typically a method on a
+    /// generated tonic client built from the channel, and sends the result back as a
+    /// [`GrpcResponse<Resp>`] event. Requests made before the channel has connected fail with
+    /// [`Status::unavailable`].
+    fn add_grpc_call<Req, Resp, Call, Fut>(&mut self, call: Call) -> &mut Self
+    where
+        Req: Clone + Send + Sync + 'static,
+        Resp: Send + Sync + 'static,
+        Call: Fn(Channel, Req) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<tonic::Response<Resp>, Status>> + Send + 'static;
+}
+
+impl AddGrpcCall for App {
+    fn add_grpc_call<Req, Resp, Call, Fut>(&mut self, call: Call) -> &mut Self
+    where
+        Req: Clone + Send + Sync + 'static,
+        Resp: Send + Sync + 'static,
+        Call: Fn(Channel, Req) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<tonic::Response<Resp>, Status>> + Send + 'static,
+    {
+        let call = Arc::new(call);
+        self.add_event::<GrpcRequest<Req>>()
+            .add_event::<GrpcResponse<Resp>>()
+            .add_systems(
+                Update,
+                move |mut requests: EventReader<GrpcRequest<Req>>,
+                      channel: Option<Res<GrpcChannel>>,
+                      tasks: Tasks| {
+                    for request in requests.read().cloned() {
+                        let id = request.id;
+                        let call = call.clone();
+                        let channel = channel.as_ref().map(|channel| channel.0.clone());
+                        tasks.spawn_tokio(move |ctx| async move {
+                            let result = match channel {
+                                Some(channel) => call(channel, request.message)
+                                    .await
+                                    .map(tonic::Response::into_inner),
+                                None => Err(Status::unavailable("gRPC channel is not connected")),
+                            };
+                            ctx.submit_on_main_thread(move |ctx| {
+                                ctx.world.send_event(GrpcResponse { id, result });
+                            });
+                        });
+                    }
+                },
+            )
+    }
+}
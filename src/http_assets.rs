@@ -0,0 +1,200 @@
+#[cfg(not(target_arch = "wasm32"))]
+use crate::Runtime;
+use bevy_app::{App, Plugin};
+use bevy_asset::{
+    io::{
+        AssetReader, AssetReaderError, AssetSource, AssetSourceId, PathStream, Reader, VecReader,
+    },
+    AssetApp,
+};
+use bevy_ecs::system::Resource;
+use dashmap::DashMap;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "tokio")))]
+compile_error!("The `http-assets` feature requires the `tokio` feature on non-wasm targets.");
+
+/// How far along an in-flight asset download is.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub received: u64,
+    /// The size of the asset, if the server reported it.
+    pub total: Option<u64>,
+}
+
+/// Progress of every asset currently being downloaded by an [`HttpAssetReader`], keyed by asset
+/// path. Entries are removed once a download finishes.
+#[derive(Resource, Clone, Default)]
+pub struct HttpAssetProgress {
+    downloads: Arc<DashMap<PathBuf, DownloadProgress>>,
+}
+
+impl HttpAssetProgress {
+    pub fn get(&self, path: &Path) -> Option<DownloadProgress> {
+        self.downloads.get(path).map(|progress| *progress)
+    }
+
+    pub fn snapshot(&self) -> Vec<(PathBuf, DownloadProgress)> {
+        self.downloads
+            .iter()
+            .map(|entry| (entry.key().clone(), *entry.value()))
+            .collect()
+    }
+}
+
+/// An [`AssetReader`] which fetches assets over HTTP(S) relative to a base URL. On native targets the
+/// downloads run on the crate's Tokio [`Runtime`], on wasm they use the browser's fetch API.
+#[derive(Clone)]
+pub struct HttpAssetReader {
+    base_url: Arc<str>,
+    client: reqwest::Client,
+    progress: HttpAssetProgress,
+    #[cfg(not(target_arch = "wasm32"))]
+    runtime: tokio::runtime::Handle,
+}
+
+impl HttpAssetReader {
+    fn url(&self, path: &Path) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.to_string_lossy()
+        )
+    }
+
+    async fn fetch(
+        client: reqwest::Client,
+        url: String,
+        path: PathBuf,
+        progress: HttpAssetProgress,
+    ) -> Result<Vec<u8>, AssetReaderError> {
+        let io_error = |err: reqwest::Error| {
+            AssetReaderError::Io(Arc::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                err,
+            )))
+        };
+        let response = client.get(url).send().await.map_err(io_error)?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(AssetReaderError::NotFound(path));
+        }
+        if !status.is_success() {
+            return Err(AssetReaderError::HttpError(status.as_u16()));
+        }
+
+        let total = response.content_length();
+        progress
+            .downloads
+            .insert(path.clone(), DownloadProgress { received: 0, total });
+        let result = Self::read_body(response, &path, &progress).await;
+        progress.downloads.remove(&path);
+        result.map_err(io_error)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn read_body(
+        mut response: reqwest::Response,
+        path: &Path,
+        progress: &HttpAssetProgress,
+    ) -> reqwest::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(response.content_length().unwrap_or_default() as usize);
+        while let Some(chunk) = response.chunk().await? {
+            bytes.extend_from_slice(&chunk);
+            if let Some(mut entry) = progress.downloads.get_mut(path) {
+                entry.received = bytes.len() as u64;
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// The browser delivers the body in one piece, so progress jumps straight to complete.
+    #[cfg(target_arch = "wasm32")]
+    async fn read_body(
+        response: reqwest::Response,
+        _path: &Path,
+        _progress: &HttpAssetProgress,
+    ) -> reqwest::Result<Vec<u8>> {
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+impl AssetReader for HttpAssetReader {
+    async fn read<'a>(&'a self, path: &'a Path) -> Result<Box<Reader<'a>>, AssetReaderError> {
+        let fetch = Self::fetch(
+            self.client.clone(),
+            self.url(path),
+            path.to_path_buf(),
+            self.progress.clone(),
+        );
+        // reqwest needs to run inside a Tokio runtime on native, but asset loading happens on Bevy's
+        // IO task pool, so the request is driven by the crate's runtime and awaited from here.
+        #[cfg(not(target_arch = "wasm32"))]
+        let bytes = self.runtime.spawn(fetch).await.map_err(|err| {
+            AssetReaderError::Io(Arc::new(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                err,
+            )))
+        })??;
+        #[cfg(target_arch = "wasm32")]
+        let bytes = fetch.await?;
+        let reader: Box<Reader> = Box::new(VecReader::new(bytes));
+        Ok(reader)
+    }
+
+    async fn read_meta<'a>(&'a self, path: &'a Path) -> Result<Box<Reader<'a>>, AssetReaderError> {
+        // Remote assets are always loaded with their default settings.
+        Err(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn read_directory<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> Result<Box<PathStream>, AssetReaderError> {
+        Err(AssetReaderError::NotFound(path.to_path_buf()))
+    }
+
+    async fn is_directory<'a>(&'a self, _path: &'a Path) -> Result<bool, AssetReaderError> {
+        Ok(false)
+    }
+}
+
+/// Registers an asset source which loads assets over HTTP(S), e.g. `asset_server.load("cdn://models/ship.glb")`
+/// for a source named `cdn`. Must be added after the [`TasksPlugin`](crate::TasksPlugin) and before
+/// Bevy's `AssetPlugin`.
+pub struct HttpAssetSourcePlugin {
+    pub source: String,
+    pub base_url: String,
+}
+
+impl HttpAssetSourcePlugin {
+    pub fn new(source: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            base_url: base_url.into(),
+        }
+    }
+}
+
+impl Plugin for HttpAssetSourcePlugin {
+    fn build(&self, app: &mut App) {
+        let progress = app
+            .world_mut()
+            .get_resource_or_insert_with(HttpAssetProgress::default)
+            .clone();
+        let reader = HttpAssetReader {
+            base_url: self.base_url.as_str().into(),
+            client: reqwest::Client::new(),
+            progress,
+            #[cfg(not(target_arch = "wasm32"))]
+            runtime: app.world().resource::<Runtime>().handle().clone(),
+        };
+        app.register_asset_source(
+            AssetSourceId::Name(self.source.clone().into()),
+            AssetSource::build().with_reader(move || Box::new(reader.clone())),
+        );
+    }
+}
@@ -0,0 +1,51 @@
+use std::{any::Any, sync::Arc};
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, UnboundedSender},
+    Mutex,
+};
+
+/// The receiving end of a task's typed mailbox, created by
+/// [`Tasks::spawn_with_inbox`](crate::Tasks::spawn_with_inbox) and read with
+/// [`TaskContext::inbox`](crate::TaskContext::inbox).
+pub struct Inbox<M> {
+    message_rx: Arc<Mutex<UnboundedReceiver<M>>>,
+}
+
+impl<M> Clone for Inbox<M> {
+    fn clone(&self) -> Self {
+        Self {
+            message_rx: self.message_rx.clone(),
+        }
+    }
+}
+
+impl<M: Send + 'static> Inbox<M> {
+    pub(crate) fn new() -> (Self, UnboundedSender<M>) {
+        let (message_tx, message_rx) = tokio::sync::mpsc::unbounded_channel();
+        let inbox = Self {
+            message_rx: Arc::new(Mutex::new(message_rx)),
+        };
+        (inbox, message_tx)
+    }
+
+    pub(crate) fn into_any(self) -> Arc<dyn Any + Send + Sync> {
+        self.message_rx
+    }
+
+    pub(crate) fn from_any(inbox: Arc<dyn Any + Send + Sync>) -> Option<Self> {
+        inbox
+            .downcast::<Mutex<UnboundedReceiver<M>>>()
+            .ok()
+            .map(|message_rx| Self { message_rx })
+    }
+
+    /// Waits for the next message. Returns `None` once every sender has been dropped.
+    pub async fn recv(&self) -> Option<M> {
+        self.message_rx.lock().await.recv().await
+    }
+
+    /// Returns the next message if one is immediately available.
+    pub fn try_recv(&self) -> Option<M> {
+        self.message_rx.try_lock().ok()?.try_recv().ok()
+    }
+}
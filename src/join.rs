@@ -1,16 +1,148 @@
+use futures_util::future::{Aborted, RemoteHandle};
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A handle to a spawned task. The handle can be awaited directly to get the task's output, which
+/// makes it composable with `futures::join!`, `select!` and friends. Awaiting the handle of a task
+/// which panicked or was aborted panics; use [`try_join`](Self::try_join) to handle those cases.
 pub enum JoinHandle<T> {
     Tokio(tokio::task::JoinHandle<T>),
-    RemoteHandle(Option<futures_util::future::RemoteHandle<T>>),
+    RemoteHandle(Option<RemoteHandle<Result<T, Aborted>>>),
+    /// A task spawned onto the async-std executor, along with the handle which aborts it. Dropping
+    /// an async-std handle detaches the task, so aborting goes through the wrapped future instead.
+    #[cfg(feature = "async-std")]
+    AsyncStd(
+        async_std::task::JoinHandle<Result<T, Aborted>>,
+        futures_util::future::AbortHandle,
+    ),
+}
+
+/// The reason a task failed to produce an output, for either backend.
+#[derive(Debug)]
+pub enum JoinError {
+    /// The task was aborted before it completed.
+    Cancelled,
+    /// The task panicked, with the given panic payload.
+    Panicked(Box<dyn Any + Send + 'static>),
+}
+
+impl JoinError {
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::Cancelled)
+    }
+
+    pub fn is_panic(&self) -> bool {
+        matches!(self, Self::Panicked(_))
+    }
+
+    /// Returns the panic payload if the task panicked.
+    pub fn into_panic(self) -> Option<Box<dyn Any + Send + 'static>> {
+        match self {
+            Self::Panicked(payload) => Some(payload),
+            Self::Cancelled => None,
+        }
+    }
+}
+
+impl std::fmt::Display for JoinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "task was cancelled"),
+            Self::Panicked(_) => write!(f, "task panicked"),
+        }
+    }
+}
+
+impl std::error::Error for JoinError {}
+
+impl From<tokio::task::JoinError> for JoinError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        match err.try_into_panic() {
+            Ok(payload) => Self::Panicked(payload),
+            Err(_) => Self::Cancelled,
+        }
+    }
 }
 
 impl<T> JoinHandle<T> {
     pub async fn join(&mut self) -> T
+    where
+        T: 'static,
+    {
+        self.await
+    }
+
+    /// Wraps the handle so that the task is aborted when the returned handle is dropped, for tasks
+    /// which should only live as long as whatever owns the handle.
+    pub fn abort_on_drop(self) -> ScopedJoinHandle<T> {
+        ScopedJoinHandle { handle: Some(self) }
+    }
+
+    /// Transforms the task's output with `f` once it finishes, without spawning another task.
+    pub fn map<U, F>(self, f: F) -> Map<T, F>
+    where
+        F: FnOnce(T) -> U,
+    {
+        Map {
+            handle: self,
+            f: Some(f),
+        }
+    }
+
+    /// Waits for the task to finish, returning an error instead of panicking if the task panicked or
+    /// was aborted.
+    pub async fn try_join(&mut self) -> Result<T, JoinError>
+    where
+        T: 'static,
+    {
+        futures_util::future::poll_fn(|cx| self.poll_try_join(cx)).await
+    }
+
+    fn poll_try_join(&mut self, cx: &mut Context<'_>) -> Poll<Result<T, JoinError>>
     where
         T: 'static,
     {
         match self {
-            Self::Tokio(handle) => handle.await.unwrap(),
-            Self::RemoteHandle(handle) => handle.take().unwrap().await,
+            Self::Tokio(handle) => Pin::new(handle).poll(cx).map_err(JoinError::from),
+            Self::RemoteHandle(handle) => {
+                let handle = handle
+                    .as_mut()
+                    .expect("JoinHandle polled after its output was taken");
+                poll_abortable(|| Pin::new(handle).poll(cx))
+            }
+            #[cfg(feature = "async-std")]
+            Self::AsyncStd(handle, _) => poll_abortable(|| Pin::new(handle).poll(cx)),
+        }
+    }
+}
+
+/// Polls the handle of a task wrapped in [`Abortable`](futures_util::future::Abortable), whose
+/// panics are resumed when the handle is polled.
+fn poll_abortable<T>(
+    poll: impl FnOnce() -> Poll<Result<T, Aborted>>,
+) -> Poll<Result<T, JoinError>> {
+    match std::panic::catch_unwind(AssertUnwindSafe(poll)) {
+        Ok(Poll::Ready(Ok(output))) => Poll::Ready(Ok(output)),
+        Ok(Poll::Ready(Err(Aborted))) => Poll::Ready(Err(JoinError::Cancelled)),
+        Ok(Poll::Pending) => Poll::Pending,
+        Err(payload) => Poll::Ready(Err(JoinError::Panicked(payload))),
+    }
+}
+
+impl<T: 'static> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        match self.get_mut().poll_try_join(cx) {
+            Poll::Ready(Ok(output)) => Poll::Ready(output),
+            Poll::Ready(Err(JoinError::Panicked(payload))) => std::panic::resume_unwind(payload),
+            Poll::Ready(Err(JoinError::Cancelled)) => panic!("Awaited a task which was aborted"),
+            Poll::Pending => Poll::Pending,
         }
     }
 }
@@ -27,6 +159,138 @@ impl<T> Drop for JoinHandle<T> {
                 }
             }
             Self::Tokio(_) => {}
+            #[cfg(feature = "async-std")]
+            Self::AsyncStd(..) => {}
+        }
+    }
+}
+
+/// A [`JoinHandle`] whose output is transformed by a function, created by [`JoinHandle::map`].
+pub struct Map<T, F> {
+    handle: JoinHandle<T>,
+    f: Option<F>,
+}
+
+// `f` is only ever moved out, never pinned, so the adaptor is `Unpin` like the handle it wraps.
+impl<T, F> Unpin for Map<T, F> {}
+
+impl<T, U, F> Map<T, F>
+where
+    T: 'static,
+    F: FnOnce(T) -> U,
+{
+    pub async fn try_join(&mut self) -> Result<U, JoinError> {
+        let output = self.handle.try_join().await?;
+        Ok(self.take_f()(output))
+    }
+
+    fn take_f(&mut self) -> F {
+        self.f
+            .take()
+            .expect("Map polled after its output was taken")
+    }
+}
+
+impl<T, U, F> Future for Map<T, F>
+where
+    T: 'static,
+    F: FnOnce(T) -> U,
+{
+    type Output = U;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<U> {
+        let this = self.get_mut();
+        Pin::new(&mut this.handle)
+            .poll(cx)
+            .map(|output| this.take_f()(output))
+    }
+}
+
+/// Waits for every task to finish, returning their outputs in the same order as the handles.
+pub async fn join_all<T: 'static>(handles: impl IntoIterator<Item = JoinHandle<T>>) -> Vec<T> {
+    futures_util::future::join_all(handles).await
+}
+
+/// Waits for the first of the tasks to finish, returning its output, its index and the handles of
+/// the tasks which are still running.
+///
+/// # Panics
+///
+/// Panics if `handles` is empty.
+pub async fn select<T: 'static>(
+    handles: impl IntoIterator<Item = JoinHandle<T>>,
+) -> (T, usize, Vec<JoinHandle<T>>) {
+    futures_util::future::select_all(handles).await
+}
+
+/// A [`JoinHandle`] which aborts its task when dropped, created by
+/// [`JoinHandle::abort_on_drop`].
+pub struct ScopedJoinHandle<T> {
+    handle: Option<JoinHandle<T>>,
+}
+
+impl<T> ScopedJoinHandle<T> {
+    pub async fn join(&mut self) -> T
+    where
+        T: 'static,
+    {
+        self.await
+    }
+
+    pub async fn try_join(&mut self) -> Result<T, JoinError>
+    where
+        T: 'static,
+    {
+        self.handle_mut().try_join().await
+    }
+
+    /// Converts back into a plain [`JoinHandle`], so the task is no longer aborted on drop.
+    pub fn detach(mut self) -> JoinHandle<T> {
+        self.handle
+            .take()
+            .expect("ScopedJoinHandle used after detach")
+    }
+
+    fn handle_mut(&mut self) -> &mut JoinHandle<T> {
+        self.handle
+            .as_mut()
+            .expect("ScopedJoinHandle used after detach")
+    }
+}
+
+impl<T: 'static> Future for ScopedJoinHandle<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        Pin::new(self.get_mut().handle_mut()).poll(cx)
+    }
+}
+
+impl<T> Drop for ScopedJoinHandle<T> {
+    fn drop(&mut self) {
+        match self.handle.as_mut() {
+            Some(JoinHandle::Tokio(handle)) => handle.abort(),
+            // Dropping a RemoteHandle without forgetting it stops its task at the next poll.
+            Some(JoinHandle::RemoteHandle(handle)) => drop(handle.take()),
+            #[cfg(feature = "async-std")]
+            Some(JoinHandle::AsyncStd(_, abort_handle)) => abort_handle.abort(),
+            None => {}
+        }
+    }
+}
+
+/// A handle which can abort a running task regardless of which backend it was spawned on.
+#[derive(Clone)]
+pub enum AbortHandle {
+    Tokio(tokio::task::AbortHandle),
+    Futures(futures_util::future::AbortHandle),
+}
+
+impl AbortHandle {
+    pub fn abort(&self) {
+        match self {
+            Self::Tokio(handle) => handle.abort(),
+            Self::Futures(handle) => handle.abort(),
         }
     }
 }
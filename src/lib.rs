@@ -1,32 +1,116 @@
+use app_exit::AppExitSignal;
 use bevy_app::{
     App, First, Last, Plugin, PostStartup, PostUpdate, PreStartup, PreUpdate, Startup, Update,
 };
 use bevy_ecs::{
+    event::Event,
     prelude::World,
-    schedule::{InternedScheduleLabel, ScheduleLabel},
+    schedule::{
+        InternedScheduleLabel, InternedSystemSet, IntoSystemConfigs, IntoSystemSet,
+        IntoSystemSetConfigs, ScheduleLabel, SystemSet,
+    },
     system::{Res, SystemParam, SystemState},
 };
-use context::main_thread::MainThreadContext;
-use std::future::Future;
+use bevy_utils::Duration;
+use futures_util::{Stream, StreamExt};
+use inbox::Inbox;
+use mirror::MirroredResources;
+use std::{future::Future, sync::Arc};
 use task_channels::TaskChannels;
 use ticks::{TicksPlugin, UpdateTicks};
+use tokio::sync::mpsc::UnboundedSender;
+use typed_channel::TypedChannels;
 
-pub use context::main_thread::MainThreadRunConfiguration;
+pub use conditions::EntityDespawned;
+pub use context::main_thread::{
+    DefaultCallbackSchedule, MainThreadError, MainThreadRunConfiguration, Timeout, TimeoutAfter,
+};
 pub use context::task::TaskContext;
-pub use join::JoinHandle;
-pub use runtime::Runtime;
+pub use events::EventStream;
+pub use extract::Extract;
+pub use join::{AbortHandle, JoinError, JoinHandle, ScopedJoinHandle};
+pub use mirror::{ApplyFromTasks, MirrorResourceToTasks, Mirrored};
+pub use panics::{PanicPolicy, TaskPanicked};
+pub use pump::{PumpConfig, PumpStats};
+pub use query::OwnedQueryData;
+pub use registry::{TaskInfo, TaskRegistry, TaskStatus, DEFAULT_FINISHED_TASK_RETENTION};
+#[cfg(feature = "tokio")]
+pub use runtime::RuntimeHealth;
+#[cfg(feature = "thread-priority")]
+pub use runtime::ThreadConfig;
+pub use runtime::{LocalRuntime, NamedRuntimes, Runtime, SmolRuntime};
+pub use shutdown::ExitPolicy;
+pub use task_channels::{CallbackTicket, Priority};
+pub use task_id::TaskId;
+pub use timers::{MainThreadTimers, RepeatBy, RepeatHandle};
+pub use timing::{TaskTiming, TaskTimings};
+pub use trace::{CallbackTrace, CallbackTraceEntry};
+pub use tracking::{ResetTasks, TrackedTasks};
+pub use typed_channel::{InitTaskChannel, TaskReceiver, TaskSender};
 
+pub mod app_exit;
+#[cfg(feature = "brp")]
+pub mod brp;
+#[cfg(feature = "clipboard")]
+pub mod clipboard;
+pub mod commands;
+pub mod conditions;
+#[cfg(feature = "console")]
+pub mod console;
 pub mod context;
+#[cfg(feature = "egui")]
+pub mod debug_overlay;
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
+pub mod entity_commands;
+pub mod events;
+pub mod extract;
+#[cfg(feature = "file-dialog")]
+pub mod file_dialog;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "http-assets")]
+pub mod http_assets;
+pub mod inbox;
 pub mod join;
+#[cfg(feature = "tokio")]
+pub mod metrics;
+pub mod mirror;
+pub mod panics;
+#[cfg(feature = "process")]
+pub mod process;
+pub mod pump;
+pub mod query;
+pub mod registry;
 pub mod runtime;
+pub mod shutdown;
+#[cfg(feature = "signal")]
+pub mod signal;
 pub mod task_channels;
+pub mod task_id;
 pub mod ticks;
+pub mod timers;
+pub mod timing;
+pub mod trace;
+pub mod tracking;
+pub mod typed_channel;
+#[cfg(feature = "udp")]
+pub mod udp;
 
 #[derive(SystemParam)]
 pub struct Tasks<'w> {
     runtime: Res<'w, Runtime>,
     task_channels: Res<'w, TaskChannels>,
     ticks: Res<'w, UpdateTicks>,
+    timings: Option<Res<'w, TaskTimings>>,
+    tracked: Res<'w, TrackedTasks>,
+    app_exit: Res<'w, AppExitSignal>,
+    local_runtime: Res<'w, LocalRuntime>,
+    smol_runtime: Res<'w, SmolRuntime>,
+    named_runtimes: Res<'w, NamedRuntimes>,
+    registry: Res<'w, TaskRegistry>,
+    typed_channels: Res<'w, TypedChannels>,
+    mirrors: Res<'w, MirroredResources>,
 }
 
 impl<'w> Tasks<'w> {
@@ -34,12 +118,415 @@ impl<'w> Tasks<'w> {
         &self.runtime
     }
 
+    /// The dedicated thread that [`spawn_local`](Self::spawn_local) runs non-`Send` tasks on.
+    pub fn local_runtime(&self) -> &LocalRuntime {
+        &self.local_runtime
+    }
+
+    /// The runtimes registered with [`TasksPlugin::add_runtime`], which
+    /// [`spawn_on`](Self::spawn_on) spawns onto.
+    pub fn named_runtimes(&self) -> &NamedRuntimes {
+        &self.named_runtimes
+    }
+
+    /// The executor that [`spawn_smol`](Self::spawn_smol) runs tasks on, when the `smol` feature is
+    /// enabled.
+    pub fn smol_runtime(&self) -> &SmolRuntime {
+        &self.smol_runtime
+    }
+
+    /// Per-task CPU time accounting, available when the `task-timing` feature is enabled.
+    pub fn timings(&self) -> Option<&TaskTimings> {
+        self.timings.as_deref()
+    }
+
+    /// The set of spawned tasks which are still running.
+    pub fn tracked(&self) -> &TrackedTasks {
+        &self.tracked
+    }
+
+    /// Every task spawned through [`Tasks`] and its status.
+    pub fn registry(&self) -> &TaskRegistry {
+        &self.registry
+    }
+
+    /// Aborts every running task spawned through [`Tasks`] and discards every main thread callback
+    /// which is still queued, so that stale tasks can't write into the world after a reset (e.g. when
+    /// returning to the main menu or reloading a world).
+    pub fn abort_all(&self) {
+        self.tracked.abort_all();
+        self.task_channels.clear();
+    }
+
     #[inline(always)]
     pub fn task_context(&self) -> TaskContext {
         TaskContext {
             tick_rx: self.ticks.tick_rx(),
             task_channels: self.task_channels.clone(),
             ticks: self.ticks.ticks(),
+            task_id: None,
+            task_name: None,
+            app_exit_rx: self.app_exit.exit_rx(),
+            world_change_tick: self.ticks.world_change_ticks(),
+            tick_sleepers: self.ticks.sleepers(),
+            inbox: None,
+            typed_channels: self.typed_channels.clone(),
+            mirrors: self.mirrors.clone(),
+        }
+    }
+
+    /// Builds the future for a task being spawned, handing it the given [`TaskContext`] tagged with
+    /// the task's id (allocating a new one unless the context already carries one) and wrapping it in
+    /// any enabled per-task instrumentation.
+    fn prepare<Task, Output, Spawnable>(
+        &self,
+        mut context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> (TaskId, impl Future<Output = Output>)
+    where
+        Task: Future<Output = Output> + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        let instrumentation = self.instrumentation(&mut context);
+        (
+            instrumentation.id,
+            instrumentation.wrap(spawnable_task(context)),
+        )
+    }
+
+    /// Sets up the instrumentation for a task about to be spawned with the given context, allocating
+    /// the task's id unless the context already carries one.
+    fn instrumentation(&self, context: &mut TaskContext) -> Instrumentation {
+        let id = *context.task_id.get_or_insert_with(TaskId::next);
+        Instrumentation {
+            id,
+            #[cfg(feature = "task-timing")]
+            timings: self.timings.as_deref().cloned(),
+            untrack: self.tracked.untrack_on_drop(id),
+            registration: self
+                .registry
+                .register(id, context.task_name.clone(), self.ticks.ticks()),
+            panics: panics::PanicReporter {
+                id,
+                name: context.task_name.clone(),
+                task_channels: self.task_channels.clone(),
+            },
+            #[cfg(feature = "trace")]
+            span: bevy_utils::tracing::info_span!(
+                "task",
+                id = %id,
+                name = context.task_name.as_deref()
+            ),
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    fn spawn_tokio_with_context<Task, Output, Spawnable>(
+        &self,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_tokio_on_with_context(&self.runtime, context, spawnable_task)
+    }
+
+    #[cfg(feature = "tokio")]
+    fn spawn_tokio_on_with_context<Task, Output, Spawnable>(
+        &self,
+        runtime: &Runtime,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        let (id, future) = self.prepare(context, spawnable_task);
+        let handle = runtime.handle().spawn(future);
+        self.tracked
+            .insert(id, AbortHandle::Tokio(handle.abort_handle()));
+        if handle.is_finished() {
+            self.tracked.remove(id);
+        }
+        JoinHandle::Tokio(handle)
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn spawn_tokio_with_context<Task, Output, Spawnable>(
+        &self,
+        _context: TaskContext,
+        _spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        unreachable!("This function should be uncallable when the `tokio` feature is not enabled.");
+    }
+
+    #[cfg(feature = "wasm")]
+    fn spawn_wasm_with_context<Task, Output, Spawnable>(
+        &self,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        use futures_util::FutureExt;
+        let (id, future) = self.prepare(context, spawnable_task);
+        let (future, abort_handle) = futures_util::future::abortable(future);
+        let (future, handle) = future.remote_handle();
+        self.tracked.insert(id, AbortHandle::Futures(abort_handle));
+        wasm_bindgen_futures::spawn_local(future);
+        JoinHandle::RemoteHandle(Some(handle))
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    fn spawn_wasm_with_context<Task, Output, Spawnable>(
+        &self,
+        _context: TaskContext,
+        _spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        unreachable!("This function should be uncallable when the `wasm` feature is not enabled.");
+    }
+
+    #[cfg(feature = "tokio")]
+    fn spawn_local_tokio_with_context<Task, Output, Spawnable>(
+        &self,
+        mut context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + Send + 'static,
+    {
+        let instrumentation = self.instrumentation(&mut context);
+        let id = instrumentation.id;
+        // The pool relays the task's output back through a task spawned onto the ambient runtime.
+        let _guard = self.runtime.handle().enter();
+        let handle = self
+            .local_runtime
+            .pool()
+            .spawn_pinned(move || instrumentation.wrap(spawnable_task(context)));
+        self.tracked
+            .insert(id, AbortHandle::Tokio(handle.abort_handle()));
+        if handle.is_finished() {
+            self.tracked.remove(id);
+        }
+        JoinHandle::Tokio(handle)
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn spawn_local_tokio_with_context<Task, Output, Spawnable>(
+        &self,
+        _context: TaskContext,
+        _spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + Send + 'static,
+    {
+        unreachable!("This function should be uncallable when the `tokio` feature is not enabled.");
+    }
+
+    #[cfg(feature = "async-std")]
+    fn spawn_async_std_with_context<Task, Output, Spawnable>(
+        &self,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        let (id, future) = self.prepare(context, spawnable_task);
+        let (future, abort_handle) = futures_util::future::abortable(future);
+        self.tracked
+            .insert(id, AbortHandle::Futures(abort_handle.clone()));
+        JoinHandle::AsyncStd(async_std::task::spawn(future), abort_handle)
+    }
+
+    #[cfg(not(feature = "async-std"))]
+    fn spawn_async_std_with_context<Task, Output, Spawnable>(
+        &self,
+        _context: TaskContext,
+        _spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        unreachable!(
+            "This function should be uncallable when the `async-std` feature is not enabled."
+        );
+    }
+
+    #[cfg(feature = "smol")]
+    fn spawn_smol_with_context<Task, Output, Spawnable>(
+        &self,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        use futures_util::FutureExt;
+        let (id, future) = self.prepare(context, spawnable_task);
+        let (future, abort_handle) = futures_util::future::abortable(future);
+        let (future, handle) = future.remote_handle();
+        self.tracked.insert(id, AbortHandle::Futures(abort_handle));
+        self.smol_runtime.executor().spawn(future).detach();
+        JoinHandle::RemoteHandle(Some(handle))
+    }
+
+    #[cfg(not(feature = "smol"))]
+    fn spawn_smol_with_context<Task, Output, Spawnable>(
+        &self,
+        _context: TaskContext,
+        _spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        unreachable!("This function should be uncallable when the `smol` feature is not enabled.");
+    }
+
+    #[cfg(feature = "bevy-tasks")]
+    fn spawn_bevy_with_context<Task, Output, Spawnable>(
+        &self,
+        pool: &bevy_tasks::TaskPool,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        use futures_util::FutureExt;
+        let (id, future) = self.prepare(context, spawnable_task);
+        let (future, abort_handle) = futures_util::future::abortable(future);
+        let (future, handle) = future.remote_handle();
+        self.tracked.insert(id, AbortHandle::Futures(abort_handle));
+        pool.spawn(future).detach();
+        JoinHandle::RemoteHandle(Some(handle))
+    }
+
+    #[cfg(feature = "bevy-tasks")]
+    fn spawn_bevy_local_with_context<Task, Output, Spawnable>(
+        &self,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        use futures_util::FutureExt;
+        let (id, future) = self.prepare(context, spawnable_task);
+        let (future, abort_handle) = futures_util::future::abortable(future);
+        let (future, handle) = future.remote_handle();
+        self.tracked.insert(id, AbortHandle::Futures(abort_handle));
+        bevy_tasks::AsyncComputeTaskPool::get()
+            .spawn_local(future)
+            .detach();
+        JoinHandle::RemoteHandle(Some(handle))
+    }
+
+    #[cfg(not(feature = "bevy-tasks"))]
+    fn spawn_bevy_local_with_context<Task, Output, Spawnable>(
+        &self,
+        _context: TaskContext,
+        _spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        unreachable!(
+            "This function should be uncallable when the `bevy-tasks` feature is not enabled."
+        );
+    }
+
+    /// Spawns onto Bevy's [`AsyncComputeTaskPool`](bevy_tasks::AsyncComputeTaskPool).
+    #[cfg(feature = "bevy-tasks")]
+    fn spawn_bevy_compute_with_context<Task, Output, Spawnable>(
+        &self,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_bevy_with_context(
+            bevy_tasks::AsyncComputeTaskPool::get(),
+            context,
+            spawnable_task,
+        )
+    }
+
+    #[cfg(not(feature = "bevy-tasks"))]
+    fn spawn_bevy_compute_with_context<Task, Output, Spawnable>(
+        &self,
+        _context: TaskContext,
+        _spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        unreachable!(
+            "This function should be uncallable when the `bevy-tasks` feature is not enabled."
+        );
+    }
+
+    fn spawn_auto_with_context<Task, Output, Spawnable>(
+        &self,
+        context: TaskContext,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        if cfg!(feature = "tokio") {
+            self.spawn_tokio_with_context(context, spawnable_task)
+        } else if cfg!(feature = "async-std") {
+            self.spawn_async_std_with_context(context, spawnable_task)
+        } else if cfg!(feature = "smol") {
+            self.spawn_smol_with_context(context, spawnable_task)
+        } else if cfg!(feature = "bevy-tasks") {
+            self.spawn_bevy_compute_with_context(context, spawnable_task)
+        } else if cfg!(feature = "wasm") {
+            self.spawn_wasm_with_context(context, spawnable_task)
+        } else {
+            panic!("No runtime is enabled. Enable the `tokio`, `async-std`, `smol`, `bevy-tasks` or `wasm` feature to use a runtime.");
         }
     }
 
@@ -56,10 +543,24 @@ impl<'w> Tasks<'w> {
         Output: Send + 'static,
         Spawnable: FnOnce(TaskContext) -> Task + 'static,
     {
-        let context = self.task_context();
-        let future = spawnable_task(context);
-        let handle = self.runtime.0.spawn(future);
-        JoinHandle::Tokio(handle)
+        self.spawn_tokio_with_context(self.task_context(), spawnable_task)
+    }
+
+    /// Like [`spawn_tokio`](Self::spawn_tokio), but labels the task with a human-readable name which
+    /// shows up in the [`TaskRegistry`] and is available to the task through
+    /// [`TaskContext::task_name`].
+    #[cfg(feature = "tokio")]
+    pub fn spawn_tokio_named<Task, Output, Spawnable>(
+        &self,
+        name: impl Into<Arc<str>>,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_tokio_with_context(self.named_task_context(name), spawnable_task)
     }
 
     #[cfg(not(feature = "tokio"))]
@@ -84,12 +585,7 @@ impl<'w> Tasks<'w> {
         Task: Future<Output = Output> + 'static,
         Spawnable: FnOnce(TaskContext) -> Task + 'static,
     {
-        use futures_util::FutureExt;
-        let context = self.task_context();
-        let future = spawnable_task(context);
-        let (future, handle) = future.remote_handle();
-        wasm_bindgen_futures::spawn_local(future);
-        JoinHandle::RemoteHandle(Some(handle))
+        self.spawn_wasm_with_context(self.task_context(), spawnable_task)
     }
 
     #[cfg(not(feature = "wasm"))]
@@ -101,6 +597,129 @@ impl<'w> Tasks<'w> {
         unreachable!("This function is private when the `wasm` feature is not enabled and should be uncallable.");
     }
 
+    /// Like [`spawn_tokio`](Self::spawn_tokio), but spawns onto the runtime registered under `name`
+    /// with [`TasksPlugin::add_runtime`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if no runtime was registered under `name`.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_on<Task, Output, Spawnable>(
+        &self,
+        name: &str,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        let runtime = self.named_runtimes.get(name).unwrap_or_else(|| {
+            panic!("No runtime named `{name}` was registered with `TasksPlugin::add_runtime`")
+        });
+        self.spawn_tokio_on_with_context(runtime, self.task_context(), spawnable_task)
+    }
+
+    /// Spawns a CPU-heavy task onto the compute runtime registered by
+    /// [`TasksPlugin::with_compute_and_io_runtimes`], falling back to the main runtime if there is
+    /// none.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_compute<Task, Output, Spawnable>(
+        &self,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        let runtime = self
+            .named_runtimes
+            .get(Runtime::COMPUTE)
+            .unwrap_or(&self.runtime);
+        self.spawn_tokio_on_with_context(runtime, self.task_context(), spawnable_task)
+    }
+
+    /// Spawns an IO-bound task onto the IO runtime registered by
+    /// [`TasksPlugin::with_compute_and_io_runtimes`], falling back to the main runtime if there is
+    /// none.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_io<Task, Output, Spawnable>(&self, spawnable_task: Spawnable) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        let runtime = self
+            .named_runtimes
+            .get(Runtime::IO)
+            .unwrap_or(&self.runtime);
+        self.spawn_tokio_on_with_context(runtime, self.task_context(), spawnable_task)
+    }
+
+    /// Spawn a task onto async-std's global executor rather than a Tokio runtime.
+    #[cfg(feature = "async-std")]
+    pub fn spawn_async_std<Task, Output, Spawnable>(
+        &self,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_async_std_with_context(self.task_context(), spawnable_task)
+    }
+
+    /// Spawn a task onto the [`SmolRuntime`] executor rather than a Tokio runtime.
+    #[cfg(feature = "smol")]
+    pub fn spawn_smol<Task, Output, Spawnable>(
+        &self,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_smol_with_context(self.task_context(), spawnable_task)
+    }
+
+    /// Spawn a task onto Bevy's [`AsyncComputeTaskPool`](bevy_tasks::AsyncComputeTaskPool) rather
+    /// than a separate Tokio runtime. The task pools are set up by Bevy's `TaskPoolPlugin`, which must
+    /// be added before any task is spawned.
+    #[cfg(feature = "bevy-tasks")]
+    pub fn spawn_bevy<Task, Output, Spawnable>(
+        &self,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_bevy_compute_with_context(self.task_context(), spawnable_task)
+    }
+
+    /// Like [`spawn_bevy`](Self::spawn_bevy), but spawns onto Bevy's
+    /// [`IoTaskPool`](bevy_tasks::IoTaskPool), for tasks which mostly wait on IO.
+    #[cfg(feature = "bevy-tasks")]
+    pub fn spawn_bevy_io<Task, Output, Spawnable>(
+        &self,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_bevy_with_context(
+            bevy_tasks::IoTaskPool::get(),
+            self.task_context(),
+            spawnable_task,
+        )
+    }
+
     pub fn spawn_auto<Task, Output, Spawnable>(
         &self,
         spawnable_task: Spawnable,
@@ -109,17 +728,216 @@ impl<'w> Tasks<'w> {
         Task: Future<Output = Output> + Send + 'static,
         Output: Send + 'static,
         Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_auto_with_context(self.task_context(), spawnable_task)
+    }
+
+    /// Spawn a task whose future isn't `Send`, for example because it holds an `Rc` or a GPU handle.
+    /// With the `tokio` feature the future is built and run on a dedicated [`LocalRuntime`] thread,
+    /// so only the closure creating it needs to be `Send`. On wasm this is the same as
+    /// [`spawn_wasm`](Self::spawn_wasm).
+    pub fn spawn_local<Task, Output, Spawnable>(
+        &self,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + Send + 'static,
     {
         if cfg!(feature = "tokio") {
-            self.spawn_tokio(spawnable_task)
+            self.spawn_local_tokio_with_context(self.task_context(), spawnable_task)
         } else if cfg!(feature = "wasm") {
-            self.spawn_wasm(spawnable_task)
+            self.spawn_wasm_with_context(self.task_context(), spawnable_task)
+        } else if cfg!(feature = "bevy-tasks") {
+            self.spawn_bevy_local_with_context(self.task_context(), spawnable_task)
         } else {
-            panic!("No runtime is enabled. Enable the `tokio` or `wasm` feature to use a runtime.");
+            panic!("No runtime is enabled. Enable the `tokio`, `bevy-tasks` or `wasm` feature to use a runtime.");
+        }
+    }
+
+    /// Runs a closure which blocks on CPU-heavy work or synchronous IO, handing it a
+    /// [`TaskContext`] so it can still [submit callbacks to the main thread](TaskContext::submit_on_main_thread).
+    /// With the `tokio` feature the closure runs on Tokio's blocking thread pool, so it doesn't stall
+    /// the async worker threads. Blocking tasks can't be aborted once they've started running.
+    #[cfg(feature = "tokio")]
+    pub fn spawn_blocking<Output, Blocking>(&self, blocking: Blocking) -> JoinHandle<Output>
+    where
+        Output: Send + 'static,
+        Blocking: FnOnce(TaskContext) -> Output + Send + 'static,
+    {
+        let mut context = self.task_context();
+        let instrumentation = self.instrumentation(&mut context);
+        let id = instrumentation.id;
+        let blocking = instrumentation.wrap_blocking(move || blocking(context));
+        let handle = self.runtime.handle().spawn_blocking(blocking);
+        self.tracked
+            .insert(id, AbortHandle::Tokio(handle.abort_handle()));
+        if handle.is_finished() {
+            self.tracked.remove(id);
+        }
+        JoinHandle::Tokio(handle)
+    }
+
+    /// Runs a closure which blocks on CPU-heavy work or synchronous IO, handing it a
+    /// [`TaskContext`] so it can still [submit callbacks to the main thread](TaskContext::submit_on_main_thread).
+    /// Without the `tokio` feature there's no thread pool to move the work onto, so the closure runs
+    /// as a task on the local executor and blocks it while running; keep such work short on wasm.
+    #[cfg(not(feature = "tokio"))]
+    pub fn spawn_blocking<Output, Blocking>(&self, blocking: Blocking) -> JoinHandle<Output>
+    where
+        Output: Send + 'static,
+        Blocking: FnOnce(TaskContext) -> Output + Send + 'static,
+    {
+        self.spawn_auto(move |context| async move { blocking(context) })
+    }
+
+    /// Like [`spawn_auto`](Self::spawn_auto), but labels the task with a human-readable name which
+    /// shows up in the [`TaskRegistry`] and is available to the task through
+    /// [`TaskContext::task_name`].
+    pub fn spawn_auto_named<Task, Output, Spawnable>(
+        &self,
+        name: impl Into<Arc<str>>,
+        spawnable_task: Spawnable,
+    ) -> JoinHandle<Output>
+    where
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        self.spawn_auto_with_context(self.named_task_context(name), spawnable_task)
+    }
+
+    fn named_task_context(&self, name: impl Into<Arc<str>>) -> TaskContext {
+        TaskContext {
+            task_name: Some(name.into()),
+            ..self.task_context()
+        }
+    }
+
+    /// Like [`spawn_auto`](Self::spawn_auto), but also creates a typed inbox for the task. Messages
+    /// sent on the returned sender can be read by the task through
+    /// [`TaskContext::inbox`], letting systems feed parameters or hints into a running job. The
+    /// sender is also available from [`TrackedTasks::inbox`] while the task runs.
+    pub fn spawn_with_inbox<M, Task, Output, Spawnable>(
+        &self,
+        spawnable_task: Spawnable,
+    ) -> (JoinHandle<Output>, UnboundedSender<M>)
+    where
+        M: Send + 'static,
+        Task: Future<Output = Output> + Send + 'static,
+        Output: Send + 'static,
+        Spawnable: FnOnce(TaskContext) -> Task + 'static,
+    {
+        let id = TaskId::next();
+        let (inbox, message_tx) = Inbox::<M>::new();
+        self.tracked.insert_inbox(id, message_tx.clone());
+        let context = TaskContext {
+            task_id: Some(id),
+            inbox: Some(inbox.into_any()),
+            ..self.task_context()
+        };
+        let handle = self.spawn_auto_with_context(context, spawnable_task);
+        (handle, message_tx)
+    }
+
+    /// Spawn a task which writes every item of `stream` into the main world as a Bevy event, so
+    /// sources like WebSockets, stdin or file watchers can be read with an `EventReader`. The task
+    /// finishes when the stream ends. `E` must have been registered with `App::add_event`.
+    pub fn stream_into_events<E, S>(&self, stream: S) -> JoinHandle<()>
+    where
+        E: Event,
+        S: Stream<Item = E> + Send + 'static,
+    {
+        self.spawn_auto(move |ctx| async move {
+            let mut stream = std::pin::pin!(stream);
+            while let Some(event) = stream.next().await {
+                ctx.send_event(event);
+            }
+        })
+    }
+}
+
+/// The per-task instrumentation applied to a spawned task's future. Kept separate from [`Tasks`] so
+/// that it can be moved to whichever thread builds the future.
+struct Instrumentation {
+    id: TaskId,
+    #[cfg(feature = "task-timing")]
+    timings: Option<TaskTimings>,
+    untrack: tracking::UntrackOnDrop,
+    registration: registry::Registration,
+    panics: panics::PanicReporter,
+    #[cfg(feature = "trace")]
+    span: bevy_utils::tracing::Span,
+}
+
+impl Instrumentation {
+    fn wrap<Task: Future>(self, future: Task) -> impl Future<Output = Task::Output> {
+        #[cfg(feature = "task-timing")]
+        let future = match &self.timings {
+            Some(timings) => futures_util::future::Either::Left(timings.track(self.id, future)),
+            None => futures_util::future::Either::Right(future),
+        };
+        #[cfg(feature = "trace")]
+        let future = bevy_utils::tracing::Instrument::instrument(future, self.span);
+        let untrack = self.untrack;
+        let mut registration = self.registration;
+        let panics = self.panics;
+        async move {
+            let _untrack = untrack;
+            match futures_util::FutureExt::catch_unwind(std::panic::AssertUnwindSafe(future)).await
+            {
+                Ok(output) => {
+                    registration.complete();
+                    output
+                }
+                Err(payload) => {
+                    registration.panicked();
+                    panics.report(&*payload);
+                    std::panic::resume_unwind(payload)
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    fn wrap_blocking<Output>(self, blocking: impl FnOnce() -> Output) -> impl FnOnce() -> Output {
+        let untrack = self.untrack;
+        let mut registration = self.registration;
+        let panics = self.panics;
+        #[cfg(feature = "trace")]
+        let span = self.span;
+        move || {
+            let _untrack = untrack;
+            #[cfg(feature = "trace")]
+            let _span = span.entered();
+            match std::panic::catch_unwind(std::panic::AssertUnwindSafe(blocking)) {
+                Ok(output) => {
+                    registration.complete();
+                    output
+                }
+                Err(payload) => {
+                    registration.panicked();
+                    panics.report(&*payload);
+                    std::panic::resume_unwind(payload)
+                }
+            }
         }
     }
 }
 
+/// The system set containing the systems which run main thread callbacks in a schedule. Order your
+/// own systems `.before()` or `.after()` it, e.g. so that changes applied by tasks are visible to a
+/// rendering prep system in the same frame.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TasksSystemSet(pub InternedScheduleLabel);
+
+impl TasksSystemSet {
+    pub fn new(schedule: impl ScheduleLabel) -> Self {
+        Self(schedule.intern())
+    }
+}
+
 /// The Bevy [`Plugin`] which sets up the [`Runtime`] Bevy resource and registers
 /// the [`tick_runtime_update`] exclusive system.
 pub struct TasksPlugin {
@@ -127,9 +945,43 @@ pub struct TasksPlugin {
     /// default value for this field configures a multi-threaded [`Runtime`] with IO and timer
     /// functionality enabled if building for non-wasm32 architectures. On wasm32 the current-thread
     /// scheduler is used instead.
-    make_runtime: Box<dyn Fn() -> Runtime + Send + Sync + 'static>,
+    make_runtime: Arc<dyn Fn() -> Runtime + Send + Sync + 'static>,
     /// Schedules in which to accept tasks.
     schedules: Vec<InternedScheduleLabel>,
+    /// Limits applied when running queued main thread callbacks.
+    pump_config: PumpConfig,
+    /// The capacity of the [`CallbackTrace`] to record executed callbacks into, if enabled.
+    callback_trace: Option<usize>,
+    /// Whether to log the [`CallbackTrace`] from a panic hook.
+    callback_trace_panic_hook: bool,
+    /// What to do with tasks which are still running when the app exits.
+    exit_policy: ExitPolicy,
+    /// What to do when a task panics.
+    panic_policy: PanicPolicy,
+    /// How long to wait for the runtime to shut down on exit, if it should be shut down explicitly.
+    shutdown_timeout: Option<Duration>,
+    /// How many frames the [`TaskRegistry`] keeps the entries of stopped tasks for.
+    finished_task_retention: Option<usize>,
+    /// Whether to rebuild the runtime if it stops running.
+    #[cfg(feature = "tokio")]
+    recover_runtime: bool,
+    /// Whether to sample the runtime's metrics into a resource every frame.
+    #[cfg(feature = "tokio")]
+    runtime_metrics: bool,
+    /// Callbacks creating the additional [`NamedRuntimes`], keyed by name.
+    named_runtimes: Vec<(String, Box<dyn Fn() -> Runtime + Send + Sync + 'static>)>,
+    /// The most main thread callbacks which may be queued at once, if bounded.
+    queue_capacity: Option<usize>,
+    /// The schedule callbacks run in when none is given, if not [`Update`].
+    default_callback_schedule: Option<InternedScheduleLabel>,
+    /// Ordering constraints for the [`TasksSystemSet`] of particular schedules.
+    pump_ordering: Vec<(InternedScheduleLabel, PumpOrdering)>,
+}
+
+#[derive(Clone, Copy)]
+enum PumpOrdering {
+    Before(InternedSystemSet),
+    After(InternedSystemSet),
 }
 
 impl Default for TasksPlugin {
@@ -138,7 +990,7 @@ impl Default for TasksPlugin {
     /// architectures the [`Runtime`] will be the multi-thread runtime.
     fn default() -> Self {
         Self {
-            make_runtime: Box::new(Runtime::default),
+            make_runtime: Arc::new(Runtime::default),
             schedules: vec![
                 PreStartup.intern(),
                 Startup.intern(),
@@ -149,36 +1001,309 @@ impl Default for TasksPlugin {
                 PostUpdate.intern(),
                 Last.intern(),
             ],
+            pump_config: PumpConfig::default(),
+            callback_trace: None,
+            callback_trace_panic_hook: false,
+            exit_policy: ExitPolicy::default(),
+            panic_policy: PanicPolicy::default(),
+            shutdown_timeout: None,
+            finished_task_retention: Some(registry::DEFAULT_FINISHED_TASK_RETENTION),
+            #[cfg(feature = "tokio")]
+            recover_runtime: false,
+            #[cfg(feature = "tokio")]
+            runtime_metrics: false,
+            named_runtimes: Vec::new(),
+            queue_capacity: None,
+            default_callback_schedule: None,
+            pump_ordering: Vec::new(),
         }
     }
 }
 
 impl TasksPlugin {
+    /// Limits how many main thread callbacks each task may have run per schedule per frame. Excess
+    /// callbacks are deferred to later frames.
+    pub fn with_per_task_quota(mut self, quota: usize) -> Self {
+        self.pump_config.per_task_quota = Some(quota);
+        self
+    }
+
+    /// Bounds the main thread callback queues to `capacity` callbacks across every schedule. Tasks
+    /// submitting through
+    /// [`submit_on_main_thread_bounded`](TaskContext::submit_on_main_thread_bounded) wait for room
+    /// in the queues, so runaway tasks can't fill memory with closures during frame hitches.
+    pub fn with_queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = Some(capacity);
+        self
+    }
+
+    /// Runs the callbacks queued for `schedule` after the systems in `set`, e.g. after a physics
+    /// plugin's sync set in [`PostUpdate`], so callbacks see its results.
+    pub fn run_tasks_after<M>(
+        mut self,
+        schedule: impl ScheduleLabel,
+        set: impl IntoSystemSet<M>,
+    ) -> Self {
+        self.pump_ordering.push((
+            schedule.intern(),
+            PumpOrdering::After(set.into_system_set().intern()),
+        ));
+        self
+    }
+
+    /// Runs the callbacks queued for `schedule` before the systems in `set`.
+    pub fn run_tasks_before<M>(
+        mut self,
+        schedule: impl ScheduleLabel,
+        set: impl IntoSystemSet<M>,
+    ) -> Self {
+        self.pump_ordering.push((
+            schedule.intern(),
+            PumpOrdering::Before(set.into_system_set().intern()),
+        ));
+        self
+    }
+
+    /// Runs main thread callbacks which aren't given a schedule in `schedule` rather than
+    /// [`Update`], e.g. [`PreUpdate`] so that they're applied before game logic runs. The schedule
+    /// must be one of the schedules the plugin runs callbacks in.
+    pub fn with_default_callback_schedule(mut self, schedule: impl ScheduleLabel) -> Self {
+        self.default_callback_schedule = Some(schedule.intern());
+        self
+    }
+
+    /// Sets what happens to tasks which are still running when the app exits. By default they're
+    /// left to be dropped along with the runtime.
+    pub fn with_exit_policy(mut self, policy: ExitPolicy) -> Self {
+        self.exit_policy = policy;
+        self
+    }
+
+    /// Shuts the runtime down when the app exits, waiting at most `timeout` for its threads to
+    /// finish, rather than leaving it to be dropped. This bounds how long slow cleanup (flushing files,
+    /// closing sockets) can delay exit. Runtimes wrapping a handle to an external runtime, or still
+    /// shared elsewhere through [`Runtime::runtime_arc`], are left running.
+    #[cfg(feature = "tokio")]
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how many frames the [`TaskRegistry`] keeps the entries and [`TaskTimings`] of stopped
+    /// tasks for, which is [`DEFAULT_FINISHED_TASK_RETENTION`] by default. With `None` they're kept
+    /// until [`TaskRegistry::clear_finished`] is called, so apps which spawn tasks for their whole
+    /// lifetime should clear them regularly.
+    pub fn with_finished_task_retention(mut self, frames: Option<usize>) -> Self {
+        self.finished_task_retention = frames;
+        self
+    }
+
+    /// Rebuilds the [`Runtime`] resource if it stops running, e.g. because it was shut down
+    /// externally, so that the app can keep spawning tasks. Tasks which were running on the old
+    /// runtime are lost. Without this, a stopped runtime is only reported through [`RuntimeHealth`].
+    #[cfg(feature = "tokio")]
+    pub fn with_runtime_recovery(mut self) -> Self {
+        self.recover_runtime = true;
+        self
+    }
+
+    /// Samples the runtime's worker count and queue depths into a
+    /// [`RuntimeMetricsResource`](metrics::RuntimeMetricsResource) at the start of every frame, for
+    /// displaying background executor health in-game.
+    #[cfg(feature = "tokio")]
+    pub fn with_runtime_metrics(mut self) -> Self {
+        self.runtime_metrics = true;
+        self
+    }
+
+    /// Sets what happens when a spawned task panics. By default the panic is logged as an error.
+    pub fn with_panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = policy;
+        self
+    }
+
+    /// Logs a warning for every main thread callback which takes longer than `threshold` to run,
+    /// naming the task which submitted it.
+    pub fn with_slow_callback_threshold(mut self, threshold: Duration) -> Self {
+        self.pump_config.slow_callback_threshold = Some(threshold);
+        self
+    }
+
+    /// Limits how long each schedule's pump spends running main thread callbacks per frame, e.g. 1ms.
+    /// Callbacks left over carry over to the next frame and are counted in [`PumpStats`].
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.pump_config.time_budget = Some(budget);
+        self
+    }
+
+    /// Limits how many main thread callbacks the pump for `schedule` runs per frame. Callbacks left
+    /// over carry over to the next frame and are counted in [`PumpStats`], and a warning is logged
+    /// if the backlog keeps growing.
+    pub fn with_max_callbacks_per_frame(
+        mut self,
+        schedule: impl ScheduleLabel,
+        max: usize,
+    ) -> Self {
+        self.pump_config
+            .max_callbacks_per_frame
+            .insert(schedule.intern(), max);
+        self
+    }
+
+    /// Records the last `capacity` executed main thread callbacks into a [`CallbackTrace`] resource.
+    pub fn with_callback_trace(mut self, capacity: usize) -> Self {
+        self.callback_trace = Some(capacity);
+        self
+    }
+
+    /// Like [`with_callback_trace`](Self::with_callback_trace), but also installs a process-wide
+    /// panic hook which logs the trace when something panics.
+    pub fn with_callback_trace_panic_hook(mut self, capacity: usize) -> Self {
+        self.callback_trace = Some(capacity);
+        self.callback_trace_panic_hook = true;
+        self
+    }
+
+    /// Registers an additional runtime under `name`, which tasks can be spawned onto with
+    /// [`Tasks::spawn_on`]. Registering a second runtime under the same name replaces the first.
+    pub fn add_runtime(
+        mut self,
+        name: impl Into<String>,
+        make_runtime: impl Fn() -> Runtime + Send + Sync + 'static,
+    ) -> Self {
+        self.named_runtimes
+            .push((name.into(), Box::new(make_runtime)));
+        self
+    }
+
+    /// Registers separately configured [`Runtime::compute`] and [`Runtime::io`] runtimes, which
+    /// [`Tasks::spawn_compute`] and [`Tasks::spawn_io`] spawn onto.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub fn with_compute_and_io_runtimes(self) -> Self {
+        self.add_runtime(Runtime::COMPUTE, Runtime::compute)
+            .add_runtime(Runtime::IO, Runtime::io)
+    }
+
+    /// Spawns tasks onto an existing Tokio runtime, e.g. one started by a server framework, instead
+    /// of constructing a runtime owned by the plugin.
+    #[cfg(feature = "tokio")]
+    pub fn with_runtime_handle(mut self, handle: tokio::runtime::Handle) -> Self {
+        self.make_runtime = Arc::new(move || Runtime::from_handle(handle.clone()));
+        self
+    }
+
+    /// Applies OS thread priority and CPU affinity settings to every thread of the runtime built
+    /// by this plugin, so that background work can be kept below the main thread's priority.
+    #[cfg(feature = "thread-priority")]
+    pub fn with_thread_config(mut self, config: ThreadConfig) -> Self {
+        self.make_runtime = Arc::new(move || Runtime::with_thread_config(&config));
+        self
+    }
+
     /// The Bevy exclusive system which executes the main thread callbacks that background
     /// tasks requested using [`run_on_main_thread`](TaskContext::run_on_main_thread). You
     /// can control which [`CoreStage`] this system executes in by specifying a custom
     /// [`tick_stage`](TasksPlugin::tick_stage) value.
     pub fn run_tasks(schedule: impl ScheduleLabel) -> impl Fn(&mut World) {
         let schedule = schedule.intern();
-        move |world: &mut World| {
-            let current_tick = world.get_resource::<UpdateTicks>().unwrap().tick();
-            let task_channels = world.get_resource::<TaskChannels>().unwrap().clone();
-            while let Some(runnable) = task_channels.try_recv(schedule) {
-                let context = MainThreadContext {
-                    world,
-                    current_tick,
-                };
-                runnable(context);
-            }
-        }
+        move |world: &mut World| pump::drain(world, schedule)
+    }
+
+    /// Creates the system which runs callbacks requested using
+    /// [`run_on_main_thread_readonly`](TaskContext::run_on_main_thread_readonly) in `schedule`.
+    pub fn run_readonly_tasks(schedule: impl ScheduleLabel) -> impl Fn(&World) {
+        let schedule = schedule.intern();
+        move |world: &World| pump::drain_readonly(world, schedule)
     }
 }
 
 impl Plugin for TasksPlugin {
     fn build(&self, app: &mut App) {
+        let mut task_channels = self
+            .queue_capacity
+            .map(TaskChannels::bounded)
+            .unwrap_or_default()
+            .with_schedules(self.schedules.iter().copied());
+        if let Some(schedule) = self.default_callback_schedule {
+            assert!(
+                self.schedules.contains(&schedule),
+                "The default callback schedule {schedule:?} isn't one of the schedules TasksPlugin \
+                 runs callbacks in, so callbacks sent to it would never run"
+            );
+            task_channels = task_channels.with_default_schedule(schedule);
+        }
         app.add_plugins(TicksPlugin)
-            .init_resource::<TaskChannels>()
+            .insert_resource(task_channels)
+            .init_resource::<TrackedTasks>()
+            .insert_resource(TaskRegistry::with_retention(self.finished_task_retention))
+            .init_resource::<TypedChannels>()
+            .init_resource::<MirroredResources>()
+            .init_resource::<LocalRuntime>()
+            .init_resource::<SmolRuntime>()
+            .init_resource::<AppExitSignal>()
+            .init_resource::<MainThreadTimers>()
+            .init_resource::<context::main_thread::SystemStateCache>()
+            .insert_resource(self.pump_config.clone())
+            .init_resource::<PumpStats>()
+            .insert_resource(self.exit_policy)
+            .insert_resource(self.panic_policy)
+            .add_event::<ResetTasks>()
+            .add_event::<TaskPanicked>()
+            .add_systems(First, tracking::reset_tasks_on_event)
+            .add_systems(
+                First,
+                MainThreadTimers::dispatch_system.before(TasksSystemSet::new(First)),
+            )
+            .init_resource::<events::EventForwarders>()
+            .add_systems(Last, events::EventForwarders::run_system)
+            .init_resource::<conditions::WorldWaiters>()
+            .add_systems(Last, conditions::WorldWaiters::run_system)
+            .add_systems(
+                Last,
+                PanicPolicy::apply_system.before(AppExitSignal::publish_system),
+            )
+            .add_systems(Last, AppExitSignal::publish_system)
+            .add_systems(Last, TaskRegistry::prune_system)
+            .add_systems(
+                Last,
+                shutdown::on_app_exit_system.after(AppExitSignal::publish_system),
+            )
             .insert_resource((self.make_runtime)());
+        #[cfg(feature = "tokio")]
+        {
+            let recover = self.recover_runtime.then(|| self.make_runtime.clone());
+            let health = RuntimeHealth::new(app.world().resource::<Runtime>(), recover);
+            app.insert_resource(health)
+                .add_systems(First, RuntimeHealth::check_system);
+            if self.runtime_metrics {
+                app.init_resource::<metrics::RuntimeMetricsResource>()
+                    .add_systems(
+                        First,
+                        metrics::RuntimeMetricsResource::sample_system
+                            .after(RuntimeHealth::check_system),
+                    );
+            }
+        }
+        let mut named_runtimes = NamedRuntimes::default();
+        for (name, make_runtime) in &self.named_runtimes {
+            named_runtimes.insert(name.clone(), make_runtime());
+        }
+        app.insert_resource(named_runtimes);
+        if let Some(timeout) = self.shutdown_timeout {
+            app.insert_resource(shutdown::ShutdownTimeout(timeout));
+        }
+        #[cfg(feature = "task-timing")]
+        app.init_resource::<TaskTimings>();
+        if let Some(capacity) = self.callback_trace {
+            let trace = CallbackTrace::new(capacity);
+            if self.callback_trace_panic_hook {
+                trace.install_panic_hook();
+            }
+            app.insert_resource(trace);
+        }
+        #[cfg(feature = "process")]
+        app.add_event::<process::ProcessOutput>()
+            .add_event::<process::ProcessExited>();
 
         let mut system = SystemState::<Tasks>::new(app.world_mut());
         let tasks = system.get(app.world());
@@ -187,7 +1312,21 @@ impl Plugin for TasksPlugin {
         app.insert_resource(task_context);
 
         for label in self.schedules.clone().into_iter() {
-            app.add_systems(label, Self::run_tasks(label));
+            // Read-only callbacks run first, so the write phase of a two-phase callback which they
+            // queue runs in the same frame.
+            app.add_systems(
+                label,
+                (Self::run_readonly_tasks(label), Self::run_tasks(label))
+                    .chain()
+                    .in_set(TasksSystemSet(label)),
+            );
+        }
+        for &(label, ordering) in &self.pump_ordering {
+            let set = TasksSystemSet(label);
+            match ordering {
+                PumpOrdering::Before(other) => app.configure_sets(label, set.before(other)),
+                PumpOrdering::After(other) => app.configure_sets(label, set.after(other)),
+            };
         }
     }
 }
@@ -0,0 +1,43 @@
+use crate::Runtime;
+use bevy_ecs::system::{Res, ResMut, Resource};
+
+/// A per-frame sample of the Tokio [`Runtime`]'s health, enabled with
+/// [`TasksPlugin::with_runtime_metrics`](crate::TasksPlugin::with_runtime_metrics). Worker park
+/// counts and local queue depths are only available when building with `--cfg tokio_unstable`.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct RuntimeMetricsResource {
+    /// The number of worker threads running async tasks.
+    pub workers: usize,
+    /// The number of tasks currently alive on the runtime.
+    pub alive_tasks: usize,
+    /// The number of tasks waiting in the runtime's global queue.
+    pub global_queue_depth: usize,
+    /// The number of times each worker has parked since the runtime was built.
+    #[cfg(tokio_unstable)]
+    pub worker_park_counts: Vec<u64>,
+    /// The number of tasks waiting in each worker's local queue.
+    #[cfg(tokio_unstable)]
+    pub worker_local_queue_depths: Vec<usize>,
+    /// The number of tasks waiting for a thread in the blocking pool.
+    #[cfg(tokio_unstable)]
+    pub blocking_queue_depth: usize,
+}
+
+impl RuntimeMetricsResource {
+    pub(crate) fn sample_system(runtime: Res<Runtime>, mut metrics: ResMut<Self>) {
+        let sample = runtime.handle().metrics();
+        metrics.workers = sample.num_workers();
+        metrics.alive_tasks = sample.num_alive_tasks();
+        metrics.global_queue_depth = sample.global_queue_depth();
+        #[cfg(tokio_unstable)]
+        {
+            metrics.worker_park_counts = (0..metrics.workers)
+                .map(|worker| sample.worker_park_count(worker))
+                .collect();
+            metrics.worker_local_queue_depths = (0..metrics.workers)
+                .map(|worker| sample.worker_local_queue_depth(worker))
+                .collect();
+            metrics.blocking_queue_depth = sample.blocking_queue_depth();
+        }
+    }
+}
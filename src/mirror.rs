@@ -0,0 +1,174 @@
+use bevy_app::{App, First, Last};
+use bevy_ecs::{
+    component::Tick,
+    system::{Commands, Res, Resource},
+};
+use dashmap::DashMap;
+use std::{
+    any::{Any, TypeId},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
+use tokio::sync::watch;
+
+/// Every resource mirrored with [`MirrorResourceToTasks::mirror_resource_to_tasks`] or
+/// [`ApplyFromTasks::apply_from_tasks`], shared with tasks through their
+/// [`TaskContext`](crate::TaskContext).
+#[derive(Resource, Clone, Default)]
+pub struct MirroredResources {
+    mirrors: Arc<DashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+    published: Arc<DashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl MirroredResources {
+    pub(crate) fn get<R: Resource + Clone>(&self) -> Option<Mirrored<R>> {
+        let mirror = self.mirrors.get(&TypeId::of::<R>())?.clone();
+        let mirror = mirror.downcast::<MirrorSource<R>>().ok()?;
+        Some(Mirrored {
+            value_rx: mirror.value_tx.subscribe(),
+            change_tick: mirror.change_tick.clone(),
+        })
+    }
+
+    /// Stores `value` to be applied to `R` next frame, replacing any value which hasn't been applied
+    /// yet. Hands the value back if `R` isn't applied from tasks.
+    pub(crate) fn publish<R: Resource>(&self, value: R) -> Result<(), R> {
+        let Some(slot) = self.published.get(&TypeId::of::<R>()) else {
+            return Err(value);
+        };
+        let Some(slot) = slot.downcast_ref::<PublishedValue<R>>() else {
+            return Err(value);
+        };
+        *slot.value.lock().unwrap() = Some(value);
+        Ok(())
+    }
+}
+
+/// The latest value of `R` published by a task which hasn't been applied yet.
+#[derive(Resource)]
+struct PublishedValue<R: Resource> {
+    value: Arc<Mutex<Option<R>>>,
+}
+
+impl<R: Resource> PublishedValue<R> {
+    fn apply_system(published: Res<PublishedValue<R>>, mut commands: Commands) {
+        if let Some(value) = published.value.lock().unwrap().take() {
+            commands.insert_resource(value);
+        }
+    }
+}
+
+/// The publishing side of a mirrored resource.
+#[derive(Resource, Clone)]
+struct MirrorSource<R: Resource + Clone> {
+    value_tx: watch::Sender<Option<R>>,
+    /// The resource's change tick as of its last publish.
+    change_tick: Arc<AtomicU32>,
+}
+
+impl<R: Resource + Clone> MirrorSource<R> {
+    fn publish_system(resource: Option<Res<R>>, source: Res<MirrorSource<R>>) {
+        match resource {
+            Some(resource) if resource.is_changed() => {
+                source.value_tx.send_replace(Some(R::clone(&resource)));
+                source
+                    .change_tick
+                    .store(resource.last_changed().get(), Ordering::SeqCst);
+            }
+            Some(_) => {}
+            None => {
+                source
+                    .value_tx
+                    .send_if_modified(|value| value.take().is_some());
+            }
+        }
+    }
+}
+
+/// A task's view of a resource mirrored with
+/// [`MirrorResourceToTasks::mirror_resource_to_tasks`], obtained with
+/// [`TaskContext::mirrored`](crate::TaskContext::mirrored). The value is republished at the end of
+/// every frame in which the resource changed.
+pub struct Mirrored<R> {
+    value_rx: watch::Receiver<Option<R>>,
+    change_tick: Arc<AtomicU32>,
+}
+
+impl<R: Clone> Mirrored<R> {
+    /// The latest published value, or `None` if the resource doesn't exist in the world.
+    pub fn get(&self) -> Option<R> {
+        self.value_rx.borrow().clone()
+    }
+
+    /// Waits until a new value is published, then returns it. Values published since the last call
+    /// to `get` or `changed` resolve immediately.
+    pub async fn changed(&mut self) -> Option<R> {
+        // The sender lives as long as the app, so an error means the app is gone.
+        let _ = self.value_rx.changed().await;
+        self.value_rx.borrow_and_update().clone()
+    }
+}
+
+impl<R> Mirrored<R> {
+    /// The resource's change tick as of the latest published value. Tasks caching data derived from
+    /// the resource can compare this against the tick their cache was built at.
+    pub fn change_tick(&self) -> Tick {
+        Tick::new(self.change_tick.load(Ordering::SeqCst))
+    }
+}
+
+/// Mirrors resources into channels which tasks can read without a main thread round trip.
+pub trait MirrorResourceToTasks {
+    /// Publishes the value of `R` at the end of every frame in which it changed, readable from tasks
+    /// through [`TaskContext::mirrored`](crate::TaskContext::mirrored). Suited to read-mostly config
+    /// and state. Must be called after the [`TasksPlugin`](crate::TasksPlugin) is added.
+    fn mirror_resource_to_tasks<R: Resource + Clone>(&mut self) -> &mut Self;
+}
+
+impl MirrorResourceToTasks for App {
+    fn mirror_resource_to_tasks<R: Resource + Clone>(&mut self) -> &mut Self {
+        if self.world().contains_resource::<MirrorSource<R>>() {
+            return self;
+        }
+        let source = MirrorSource::<R> {
+            value_tx: watch::Sender::new(None),
+            change_tick: Arc::new(AtomicU32::new(0)),
+        };
+        self.world()
+            .resource::<MirroredResources>()
+            .mirrors
+            .insert(TypeId::of::<R>(), Arc::new(source.clone()));
+        self.insert_resource(source)
+            .add_systems(Last, MirrorSource::<R>::publish_system)
+    }
+}
+
+/// Applies values published by tasks to resources.
+pub trait ApplyFromTasks {
+    /// Lets tasks overwrite `R` with [`TaskContext::publish`](crate::TaskContext::publish). The latest
+    /// published value is inserted at the start of each frame, so intermediate writes within a frame
+    /// are coalesced. Must be called after the [`TasksPlugin`](crate::TasksPlugin) is added.
+    fn apply_from_tasks<R: Resource>(&mut self) -> &mut Self;
+}
+
+impl ApplyFromTasks for App {
+    fn apply_from_tasks<R: Resource>(&mut self) -> &mut Self {
+        if self.world().contains_resource::<PublishedValue<R>>() {
+            return self;
+        }
+        let value = Arc::new(Mutex::new(None));
+        self.world()
+            .resource::<MirroredResources>()
+            .published
+            .insert(
+                TypeId::of::<R>(),
+                Arc::new(PublishedValue::<R> {
+                    value: value.clone(),
+                }),
+            );
+        self.insert_resource(PublishedValue::<R> { value })
+            .add_systems(First, PublishedValue::<R>::apply_system)
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    context::main_thread::DefaultCallbackSchedule, task_channels::TaskChannels, task_id::TaskId,
+};
+use bevy_app::AppExit;
+use bevy_ecs::{
+    event::{Event, EventReader, EventWriter},
+    schedule::ScheduleLabel,
+    system::{Res, Resource},
+};
+use bevy_utils::tracing::error;
+use std::{any::Any, sync::Arc};
+
+/// What [`TasksPlugin`](crate::TasksPlugin) does when a spawned task panics, set with
+/// [`TasksPlugin::with_panic_policy`](crate::TasksPlugin::with_panic_policy). A [`TaskPanicked`]
+/// event is sent regardless of the policy.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PanicPolicy {
+    /// Send the [`TaskPanicked`] event but don't log or exit.
+    Ignore,
+    /// Log the panic as an error and keep running.
+    #[default]
+    LogError,
+    /// Log the panic as an error and exit the app with an error code, so that headless servers
+    /// crash fast instead of running on in a broken state.
+    ExitApp,
+}
+
+impl PanicPolicy {
+    pub(crate) fn apply_system(
+        mut panics: EventReader<TaskPanicked>,
+        policy: Option<Res<PanicPolicy>>,
+        mut exit: EventWriter<AppExit>,
+    ) {
+        let policy = policy.map_or_else(PanicPolicy::default, |policy| *policy);
+        for panicked in panics.read() {
+            if policy == PanicPolicy::Ignore {
+                continue;
+            }
+            error!(
+                "Background task {} ({}) panicked: {}",
+                panicked.id,
+                panicked.name.as_deref().unwrap_or("unnamed"),
+                panicked
+                    .payload
+                    .as_deref()
+                    .unwrap_or("<non-string payload>"),
+            );
+            if policy == PanicPolicy::ExitApp {
+                exit.send(AppExit::error());
+            }
+        }
+    }
+}
+
+/// Sent when a task spawned through [`Tasks`](crate::Tasks) panics. The panic is still propagated to
+/// the task's [`JoinHandle`](crate::JoinHandle), so this doesn't change how the task's output is
+/// observed; it only makes sure the panic doesn't go unnoticed when nobody awaits the handle.
+#[derive(Event, Clone, Debug)]
+pub struct TaskPanicked {
+    pub id: TaskId,
+    pub name: Option<Arc<str>>,
+    /// The panic message, if the panic payload was a string.
+    pub payload: Option<String>,
+}
+
+/// Extracts the message from a panic payload, which is a `&str` or `String` for panics raised with
+/// `panic!`.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+}
+
+/// Reports a spawned task's panic back to the main thread.
+pub(crate) struct PanicReporter {
+    pub(crate) id: TaskId,
+    pub(crate) name: Option<Arc<str>>,
+    pub(crate) task_channels: TaskChannels,
+}
+
+impl PanicReporter {
+    /// Queues a [`TaskPanicked`] event to be sent from the main thread.
+    pub(crate) fn report(self, payload: &(dyn Any + Send)) {
+        let event = TaskPanicked {
+            id: self.id,
+            name: self.name,
+            payload: panic_message(payload),
+        };
+        let _ = self.task_channels.submit_from(
+            DefaultCallbackSchedule.intern(),
+            Some(self.id),
+            move |ctx| {
+                ctx.world.send_event(event);
+            },
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{JoinHandle, TaskContext, Tasks};
+use bevy_ecs::event::Event;
+use std::{
+    io,
+    process::{ExitStatus, Stdio},
+    sync::atomic::{AtomicU64, Ordering},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead},
+    process::Command,
+};
+
+/// Identifies a process started with [`TaskContext::run_command`] or [`Tasks::spawn_process`], so
+/// that its [`ProcessOutput`] and [`ProcessExited`] events can be told apart from other processes'.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProcessId(u64);
+
+impl ProcessId {
+    pub fn next() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// A line written by a running process to its standard output or standard error.
+#[derive(Event, Clone, Debug)]
+pub struct ProcessOutput {
+    pub process: ProcessId,
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+/// Sent once a process has exited and all of its output has been emitted.
+#[derive(Event, Clone, Debug)]
+pub struct ProcessExited {
+    pub process: ProcessId,
+    pub status: ExitStatus,
+}
+
+impl TaskContext {
+    /// Runs an external process to completion, emitting each line it prints as a [`ProcessOutput`]
+    /// event on the main thread and finally a [`ProcessExited`] event. The process is killed if the
+    /// returned future is dropped before it exits.
+    pub async fn run_command(&self, command: Command) -> io::Result<ExitStatus> {
+        self.run_command_with_id(ProcessId::next(), command).await
+    }
+
+    /// Like [`run_command`](Self::run_command), but tags the process's events with a caller-chosen
+    /// [`ProcessId`].
+    pub async fn run_command_with_id(
+        &self,
+        process: ProcessId,
+        mut command: Command,
+    ) -> io::Result<ExitStatus> {
+        command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let mut child = command.spawn()?;
+        let stdout = child.stdout.take();
+        let stderr = child.stderr.take();
+        let (status, (), ()) = futures_util::join!(
+            child.wait(),
+            self.forward_lines(process, OutputStream::Stdout, stdout),
+            self.forward_lines(process, OutputStream::Stderr, stderr),
+        );
+        let status = status?;
+        self.submit_on_main_thread(move |ctx| {
+            ctx.world.send_event(ProcessExited { process, status });
+        });
+        Ok(status)
+    }
+
+    async fn forward_lines(
+        &self,
+        process: ProcessId,
+        stream: OutputStream,
+        reader: Option<impl AsyncRead + Unpin>,
+    ) {
+        let Some(reader) = reader else {
+            return;
+        };
+        let mut lines = tokio::io::BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            self.submit_on_main_thread(move |ctx| {
+                ctx.world.send_event(ProcessOutput {
+                    process,
+                    stream,
+                    line,
+                });
+            });
+        }
+    }
+}
+
+impl<'w> Tasks<'w> {
+    /// Spawns a background task running an external process with
+    /// [`run_command`](TaskContext::run_command). The returned [`ProcessId`] identifies the
+    /// process's [`ProcessOutput`] and [`ProcessExited`] events.
+    pub fn spawn_process(
+        &self,
+        command: Command,
+    ) -> (ProcessId, JoinHandle<io::Result<ExitStatus>>) {
+        let process = ProcessId::next();
+        let handle = self
+            .spawn_tokio(move |ctx| async move { ctx.run_command_with_id(process, command).await });
+        (process, handle)
+    }
+}
@@ -0,0 +1,740 @@
+use crate::{
+    context::main_thread::MainThreadContext,
+    registry::TaskRegistry,
+    task_channels::{Priority, QueuedCallback, TaskChannels, LANE_TURNS},
+    task_id::TaskId,
+    ticks::UpdateTicks,
+    trace::{CallbackTrace, CallbackTraceEntry},
+};
+use bevy_ecs::{schedule::InternedScheduleLabel, system::Resource, world::World};
+use bevy_utils::{tracing::warn, Duration, HashMap, Instant};
+use std::{cell::Cell, collections::VecDeque, ptr::NonNull, sync::Arc};
+
+/// How many frames in a row a schedule's backlog may grow before a warning is logged.
+const BACKLOG_WARN_FRAMES: u32 = 60;
+
+/// Limits applied by the per-schedule pump which runs queued main thread callbacks.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct PumpConfig {
+    /// The most callbacks a single task may have run per schedule per frame. Callbacks over the quota
+    /// are deferred to the next frame, so that one task flooding the queue can't delay every other
+    /// task's round trips. Callbacks which weren't submitted from a spawned task aren't limited.
+    pub per_task_quota: Option<usize>,
+    /// Callbacks which take longer than this to run are logged as a warning, naming the task which
+    /// submitted them, to help find the `run_on_main_thread` closure which is eating frame time.
+    pub slow_callback_threshold: Option<Duration>,
+    /// How long each schedule's pump may spend running callbacks per frame. Once it's used up, the
+    /// remaining callbacks carry over to the next frame, so many tasks completing at once can't blow
+    /// the frame time. At least one callback runs per frame regardless.
+    pub time_budget: Option<Duration>,
+    /// The most callbacks each schedule's pump may run per frame, for schedules which have a limit.
+    /// The remaining callbacks carry over to the next frame.
+    pub max_callbacks_per_frame: HashMap<InternedScheduleLabel, usize>,
+}
+
+/// Counters kept by the callback pump.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct PumpStats {
+    /// How many callbacks were left queued because a pump ran out of its
+    /// [`time_budget`](PumpConfig::time_budget), summed over every frame they were carried over.
+    pub deferred_over_budget: u64,
+    /// How many callbacks were left queued because a pump reached its
+    /// [`max_callbacks_per_frame`](PumpConfig::max_callbacks_per_frame), summed over every frame
+    /// they were carried over.
+    pub deferred_over_limit: u64,
+    backlogs: HashMap<InternedScheduleLabel, Backlog>,
+}
+
+/// Tracks whether a schedule's carried over callbacks are piling up.
+#[derive(Clone, Copy, Debug, Default)]
+struct Backlog {
+    len: usize,
+    growing_frames: u32,
+}
+
+impl PumpStats {
+    fn record_backlog(&mut self, schedule: InternedScheduleLabel, len: usize) {
+        let backlog = self.backlogs.entry(schedule).or_default();
+        if len > backlog.len {
+            backlog.growing_frames += 1;
+        } else {
+            backlog.growing_frames = 0;
+        }
+        backlog.len = len;
+        if backlog.growing_frames >= BACKLOG_WARN_FRAMES {
+            warn!(
+                "{len} main thread callbacks are backed up in {schedule:?}, and the backlog has \
+                 grown for {BACKLOG_WARN_FRAMES} frames in a row"
+            );
+            backlog.growing_frames = 0;
+        }
+    }
+}
+
+thread_local! {
+    /// Whether the pump on this thread is in the middle of running a callback.
+    static RUNNING_CALLBACK: Cell<bool> = const { Cell::new(false) };
+    /// The world lent out by [`MainThreadContext::block_on`], along with the rest of the blocked
+    /// callback's context. Taken while a nested callback is running inline.
+    static INLINE_WORLD: Cell<Option<InlineWorld>> = const { Cell::new(None) };
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct InlineWorld {
+    pub(crate) world: NonNull<World>,
+    pub(crate) current_tick: usize,
+    pub(crate) schedule: InternedScheduleLabel,
+    pub(crate) budget_deadline: Option<Instant>,
+}
+
+/// Puts back the previous value of a thread local when dropped, including while unwinding.
+struct Restore<T: Copy + 'static> {
+    key: &'static std::thread::LocalKey<Cell<T>>,
+    previous: T,
+}
+
+impl<T: Copy + 'static> Restore<T> {
+    fn replace(key: &'static std::thread::LocalKey<Cell<T>>, value: T) -> Self {
+        Self {
+            key,
+            previous: key.replace(value),
+        }
+    }
+}
+
+impl<T: Copy + 'static> Drop for Restore<T> {
+    fn drop(&mut self) {
+        self.key.set(self.previous);
+    }
+}
+
+/// Makes `inline` available to [`try_run_inline`] while `f` runs.
+pub(crate) fn with_inline_world<Output>(inline: InlineWorld, f: impl FnOnce() -> Output) -> Output {
+    let _restore = Restore::replace(&INLINE_WORLD, Some(inline));
+    f()
+}
+
+/// Panics if a callback is running on this thread. The pump can't move on to later frames until
+/// the callback returns, so a callback blocking on something which only happens in a later frame
+/// would deadlock.
+pub(crate) fn assert_not_in_callback() {
+    if RUNNING_CALLBACK.get() {
+        panic!(
+            "A main thread callback blocked on something which only happens in a later frame, \
+             which would deadlock"
+        );
+    }
+}
+
+/// Runs `f` straight away if it's submitted from a future which a callback on this thread is
+/// blocking on with [`MainThreadContext::block_on`]. Queueing it instead would deadlock, since the
+/// pump can't get to it until the blocked callback returns. Gives `f` back if no callback is
+/// running on this thread, so it can be queued as usual.
+///
+/// # Panics
+///
+/// If a callback on this thread is blocking without having lent out its world, or `f` has to wait
+/// for another schedule or for other callbacks, because it would never run.
+pub(crate) fn try_run_inline<F, Output>(
+    schedule: InternedScheduleLabel,
+    waits: bool,
+    f: F,
+) -> Result<Output, F>
+where
+    F: FnOnce(MainThreadContext) -> Output,
+{
+    if !RUNNING_CALLBACK.get() {
+        return Err(f);
+    }
+    let Some(mut inline) = INLINE_WORLD.take() else {
+        panic!(
+            "A main thread callback blocked on a future which runs a callback on the main thread, \
+             which would deadlock. Block with `MainThreadContext::block_on` to run it inline."
+        );
+    };
+    let _restore = Restore {
+        key: &INLINE_WORLD,
+        previous: Some(inline),
+    };
+    if schedule != inline.schedule || waits {
+        panic!(
+            "A main thread callback in {:?} blocked on a callback which has to wait for {schedule:?} \
+             or for other callbacks, which would deadlock",
+            inline.schedule
+        );
+    }
+    // SAFETY: The pointer was lent out by `MainThreadContext::block_on`, which holds the exclusive
+    // borrow of the world it came from until the blocking call returns, so the blocked callback
+    // can't touch the world meanwhile. The pointer is taken out of `INLINE_WORLD` until `f`
+    // returns, so nothing else can reach the world in the meantime.
+    let world = unsafe { inline.world.as_mut() };
+    Ok(f(MainThreadContext {
+        world,
+        current_tick: inline.current_tick,
+        schedule: inline.schedule,
+        budget_deadline: inline.budget_deadline,
+    }))
+}
+
+/// Runs the callbacks queued for `schedule`, subject to the world's [`PumpConfig`].
+pub(crate) fn drain(world: &mut World, schedule: InternedScheduleLabel) {
+    #[cfg(feature = "trace")]
+    let _span = bevy_utils::tracing::info_span!("run_tasks", schedule = ?schedule).entered();
+    let current_tick = world.resource::<UpdateTicks>().tick();
+    let task_channels = world.resource::<TaskChannels>().clone();
+    let config = world
+        .get_resource::<PumpConfig>()
+        .cloned()
+        .unwrap_or_default();
+    let trace = world.get_resource::<CallbackTrace>().cloned();
+    let registry = world.get_resource::<TaskRegistry>().cloned();
+    let started = Instant::now();
+    let pump = Pump {
+        schedule,
+        current_tick,
+        budget_deadline: config.time_budget.map(|budget| started + budget),
+        task_channels: &task_channels,
+        trace: trace.as_ref(),
+        slow_callback_threshold: config.slow_callback_threshold,
+        registry: registry.as_ref(),
+    };
+
+    let max_callbacks = config.max_callbacks_per_frame.get(&schedule).copied();
+    let mut ran = 0;
+    let mut over_limit = false;
+    // Only called once a callback is about to run, so callbacks which are held back don't use up
+    // the budget.
+    let mut over_budget = || {
+        if max_callbacks.is_some_and(|max| ran >= max) {
+            over_limit = true;
+            return true;
+        }
+        let over = ran > 0
+            && config
+                .time_budget
+                .is_some_and(|budget| started.elapsed() >= budget);
+        ran += 1;
+        over
+    };
+    let mut out_of_budget = false;
+
+    let mut per_task = HashMap::new();
+    let mut deferred = Vec::new();
+    let mut waiting = Vec::new();
+    let mut fair = FairQueue::default();
+    while let Some(queued) = fair.refill(&task_channels, schedule).pop() {
+        if !task_channels.is_ready(&queued) {
+            waiting.push(queued);
+            continue;
+        }
+        if !is_due(&queued, current_tick) {
+            deferred.push(queued);
+            continue;
+        }
+        if let (Some(quota), Some(task)) = (config.per_task_quota, queued.task) {
+            if per_task.get(&task).is_some_and(|ran| *ran >= quota) {
+                deferred.push(queued);
+                continue;
+            }
+        }
+        if over_budget() {
+            deferred.push(queued);
+            out_of_budget = true;
+            break;
+        }
+        if let (Some(_), Some(task)) = (config.per_task_quota, queued.task) {
+            *per_task.entry(task).or_insert(0) += 1;
+        }
+        pump.run(world, queued);
+    }
+
+    // Callbacks which ran above may have completed tickets that earlier callbacks in the queue were
+    // waiting on, so keep retrying until no more become ready.
+    loop {
+        let (ready, still_waiting): (Vec<_>, Vec<_>) = waiting
+            .into_iter()
+            .partition(|queued| task_channels.is_ready(queued));
+        waiting = still_waiting;
+        if ready.is_empty() {
+            break;
+        }
+        for queued in ready {
+            if !is_due(&queued, current_tick) {
+                deferred.push(queued);
+            } else if over_budget() {
+                deferred.push(queued);
+                out_of_budget = true;
+            } else {
+                pump.run(world, queued);
+            }
+        }
+    }
+
+    deferred.extend(fair.into_remaining());
+    deferred.extend(waiting);
+    if !deferred.is_empty() {
+        task_channels.defer(schedule, deferred);
+    }
+    let left = task_channels.len(schedule);
+    if let Some(mut stats) = world.get_resource_mut::<PumpStats>() {
+        if out_of_budget {
+            if over_limit {
+                stats.deferred_over_limit += left as u64;
+            } else {
+                stats.deferred_over_budget += left as u64;
+            }
+        }
+        stats.record_backlog(schedule, left);
+    }
+}
+
+/// Callbacks taken from a schedule's queue, sorted back into their priority lanes and split up by
+/// the task which submitted them within each lane, so that the pump takes turns between tasks
+/// instead of running one chatty task's callbacks before everyone else's. The lanes are served
+/// with the same weighting as the schedule's queue, and each task's own callbacks in a lane still
+/// run in the order they were queued.
+#[derive(Default)]
+struct FairQueue {
+    lanes: [RoundRobin; 3],
+    /// Which entry of [`LANE_TURNS`] is served next.
+    turn: usize,
+}
+
+impl FairQueue {
+    /// Takes everything newly queued for `schedule`, including callbacks queued by callbacks which
+    /// already ran this frame.
+    fn refill(
+        &mut self,
+        task_channels: &TaskChannels,
+        schedule: InternedScheduleLabel,
+    ) -> &mut Self {
+        while let Some(queued) = task_channels.try_recv(schedule) {
+            self.lanes[queued.priority.lane()].push(queued);
+        }
+        self
+    }
+
+    fn pop(&mut self) -> Option<QueuedCallback> {
+        let preferred = LANE_TURNS[self.turn % LANE_TURNS.len()];
+        self.turn = self.turn.wrapping_add(1);
+        std::iter::once(preferred)
+            .chain(Priority::ALL)
+            .find_map(|priority| self.lanes[priority.lane()].pop())
+    }
+
+    /// The callbacks which didn't get a turn, by lane and then by task.
+    fn into_remaining(self) -> impl Iterator<Item = QueuedCallback> {
+        self.lanes.into_iter().flat_map(RoundRobin::into_remaining)
+    }
+}
+
+/// One lane's callbacks, served round-robin between the tasks which submitted them.
+#[derive(Default)]
+struct RoundRobin {
+    by_task: HashMap<Option<TaskId>, VecDeque<QueuedCallback>>,
+    /// The tasks with callbacks in `by_task`, in the order they get their next turn.
+    turns: VecDeque<Option<TaskId>>,
+}
+
+impl RoundRobin {
+    fn push(&mut self, queued: QueuedCallback) {
+        let task = queued.task;
+        let queue = self.by_task.entry(task).or_insert_with(|| {
+            self.turns.push_back(task);
+            VecDeque::new()
+        });
+        queue.push_back(queued);
+    }
+
+    fn pop(&mut self) -> Option<QueuedCallback> {
+        let task = self.turns.pop_front()?;
+        let queue = self.by_task.get_mut(&task)?;
+        let queued = queue.pop_front();
+        if queue.is_empty() {
+            self.by_task.remove(&task);
+        } else {
+            self.turns.push_back(task);
+        }
+        queued
+    }
+
+    fn into_remaining(self) -> impl Iterator<Item = QueuedCallback> {
+        let mut by_task = self.by_task;
+        self.turns
+            .into_iter()
+            .filter_map(move |task| by_task.remove(&task))
+            .flatten()
+    }
+}
+
+/// Whether the update tick a callback was held for has been reached.
+fn is_due(queued: &QueuedCallback, current_tick: usize) -> bool {
+    !queued.run_at_tick.is_some_and(|tick| current_tick < tick)
+}
+
+/// Runs the read-only callbacks queued for `schedule`. Only needs shared access to the world, so it
+/// can run in parallel with other systems which only read.
+pub(crate) fn drain_readonly(world: &World, schedule: InternedScheduleLabel) {
+    #[cfg(feature = "trace")]
+    let _span =
+        bevy_utils::tracing::info_span!("run_readonly_tasks", schedule = ?schedule).entered();
+    let task_channels = world.resource::<TaskChannels>();
+    while let Some(callback) = task_channels.try_recv_readonly(schedule) {
+        callback(world);
+    }
+}
+
+struct Pump<'a> {
+    schedule: InternedScheduleLabel,
+    current_tick: usize,
+    budget_deadline: Option<Instant>,
+    task_channels: &'a TaskChannels,
+    trace: Option<&'a CallbackTrace>,
+    slow_callback_threshold: Option<Duration>,
+    registry: Option<&'a TaskRegistry>,
+}
+
+impl Pump<'_> {
+    fn run(&self, world: &mut World, queued: QueuedCallback) {
+        let _running = Restore::replace(&RUNNING_CALLBACK, true);
+        let context = MainThreadContext {
+            world,
+            current_tick: self.current_tick,
+            schedule: self.schedule,
+            budget_deadline: self.budget_deadline,
+        };
+        let timed = self.trace.is_some() || self.slow_callback_threshold.is_some();
+        let started = timed.then(Instant::now);
+        #[cfg(feature = "trace")]
+        let _span = {
+            let name = self.task_name(queued.task);
+            bevy_utils::tracing::info_span!(
+                "main_thread_callback",
+                schedule = ?self.schedule,
+                task = queued.task.map(|task| task.get()),
+                name = name.as_deref(),
+            )
+            .entered()
+        };
+        (queued.callback)(context);
+        if let Some(ticket) = queued.ticket {
+            self.task_channels.complete_ticket(ticket);
+        }
+        let Some(started) = started else {
+            return;
+        };
+        let duration = started.elapsed();
+        if let Some(trace) = self.trace {
+            trace.record(CallbackTraceEntry {
+                task: queued.task,
+                schedule: self.schedule,
+                tick: self.current_tick,
+                duration,
+            });
+        }
+        if self
+            .slow_callback_threshold
+            .is_some_and(|threshold| duration > threshold)
+        {
+            let task = match (queued.task, self.task_name(queued.task)) {
+                (Some(task), Some(name)) => format!("{task} ({name})"),
+                (Some(task), None) => task.to_string(),
+                (None, _) => "a non-task submitter".to_string(),
+            };
+            warn!(
+                "Main thread callback from {task} in {:?} took {duration:.2?}",
+                self.schedule
+            );
+        }
+    }
+
+    fn task_name(&self, task: Option<TaskId>) -> Option<Arc<str>> {
+        task.zip(self.registry)
+            .and_then(|(task, registry)| registry.get(task)?.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        context::main_thread::MainThreadCallback, MainThreadTimers, RepeatBy, TaskContext,
+        TasksPlugin,
+    };
+    use bevy_app::{App, Update};
+    use bevy_ecs::schedule::ScheduleLabel;
+
+    #[derive(Resource, Default)]
+    struct Counter(usize);
+
+    /// The labels of the callbacks which have run, in order.
+    #[derive(Resource, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(TasksPlugin::default())
+            .init_resource::<Counter>()
+            .init_resource::<Log>();
+        app
+    }
+
+    fn record(label: &'static str) -> MainThreadCallback {
+        Box::new(move |ctx: MainThreadContext| ctx.world.resource_mut::<Log>().0.push(label))
+    }
+
+    fn queue(app: &App, queued: QueuedCallback) {
+        app.world()
+            .resource::<TaskChannels>()
+            .submit_queued(Update.intern(), queued)
+            .unwrap();
+    }
+
+    fn drain_update(app: &mut App) -> Vec<&'static str> {
+        drain(app.world_mut(), Update.intern());
+        std::mem::take(&mut app.world_mut().resource_mut::<Log>().0)
+    }
+
+    fn queued_len(app: &App) -> usize {
+        app.world().resource::<TaskChannels>().len(Update.intern())
+    }
+
+    fn submit(task_channels: &TaskChannels, task: TaskId, priority: Priority) {
+        task_channels
+            .submit_queued(
+                Update.intern(),
+                QueuedCallback {
+                    task: Some(task),
+                    priority,
+                    ..QueuedCallback::new(Box::new(|_| {}))
+                },
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn fair_queue_keeps_priorities_and_takes_turns_between_tasks() {
+        let task_channels = TaskChannels::default();
+        let (chatty, quiet) = (TaskId::next(), TaskId::next());
+        for _ in 0..3 {
+            submit(&task_channels, chatty, Priority::Normal);
+        }
+        submit(&task_channels, chatty, Priority::High);
+        submit(&task_channels, quiet, Priority::Normal);
+
+        let mut fair = FairQueue::default();
+        fair.refill(&task_channels, Update.intern());
+        let order: Vec<_> = std::iter::from_fn(|| fair.pop())
+            .map(|queued| (queued.task.unwrap(), queued.priority))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                // The high priority callback goes first, despite being queued after the task's
+                // normal priority ones.
+                (chatty, Priority::High),
+                (chatty, Priority::Normal),
+                // The quiet task gets a turn before the rest of the chatty task's callbacks.
+                (quiet, Priority::Normal),
+                (chatty, Priority::Normal),
+                (chatty, Priority::Normal),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_run_on_main_thread_runs_inline_when_blocked_on() {
+        let mut app = app();
+        let task_context = app.world().resource::<TaskContext>().clone();
+        let _ = task_context.submit_on_main_thread(|mut ctx| {
+            let nested = ctx.task_context();
+            let seen = ctx.block_on(nested.run_on_main_thread(|ctx| {
+                ctx.world.resource_mut::<Counter>().0 += 1;
+                ctx.world.resource::<Counter>().0
+            }));
+            ctx.world.resource_mut::<Counter>().0 += seen * 10;
+        });
+
+        drain(app.world_mut(), Update.intern());
+        assert_eq!(app.world().resource::<Counter>().0, 11);
+    }
+
+    #[test]
+    #[should_panic(expected = "would deadlock")]
+    fn blocking_on_an_unmet_condition_in_a_callback_panics() {
+        let mut app = app();
+        let task_context = app.world().resource::<TaskContext>().clone();
+        let _ = task_context.submit_on_main_thread(|mut ctx| {
+            let nested = ctx.task_context();
+            ctx.block_on(nested.wait_until(|world| world.resource::<Counter>().0 > 0));
+        });
+
+        drain(app.world_mut(), Update.intern());
+    }
+
+    #[test]
+    fn lanes_are_served_four_high_and_two_normal_to_each_low() {
+        let mut app = app();
+        for (priority, label) in [
+            (Priority::Low, "low"),
+            (Priority::Normal, "normal"),
+            (Priority::High, "high"),
+        ] {
+            for _ in 0..LANE_TURNS.len() {
+                queue(
+                    &app,
+                    QueuedCallback {
+                        priority,
+                        ..QueuedCallback::new(record(label))
+                    },
+                );
+            }
+        }
+        app.world_mut()
+            .resource_mut::<PumpConfig>()
+            .max_callbacks_per_frame
+            .insert(Update.intern(), LANE_TURNS.len());
+
+        assert_eq!(
+            drain_update(&mut app),
+            ["high", "normal", "high", "low", "high", "normal", "high"]
+        );
+        assert_eq!(queued_len(&app), 2 * LANE_TURNS.len());
+        assert_eq!(
+            app.world().resource::<PumpStats>().deferred_over_limit,
+            2 * LANE_TURNS.len() as u64
+        );
+    }
+
+    #[test]
+    fn time_budget_runs_one_callback_and_carries_the_rest_over() {
+        let mut app = app();
+        for label in ["first", "second", "third"] {
+            queue(&app, QueuedCallback::new(record(label)));
+        }
+        app.world_mut().resource_mut::<PumpConfig>().time_budget = Some(Duration::ZERO);
+
+        assert_eq!(drain_update(&mut app), ["first"]);
+        assert_eq!(queued_len(&app), 2);
+        assert_eq!(app.world().resource::<PumpStats>().deferred_over_budget, 2);
+        assert_eq!(drain_update(&mut app), ["second"]);
+        assert_eq!(drain_update(&mut app), ["third"]);
+    }
+
+    #[test]
+    fn per_task_quota_defers_a_chatty_tasks_extra_callbacks() {
+        let mut app = app();
+        let (chatty, quiet) = (TaskId::next(), TaskId::next());
+        for (task, label) in [
+            (chatty, "chatty"),
+            (chatty, "chatty"),
+            (chatty, "chatty"),
+            (quiet, "quiet"),
+        ] {
+            queue(
+                &app,
+                QueuedCallback {
+                    task: Some(task),
+                    ..QueuedCallback::new(record(label))
+                },
+            );
+        }
+        app.world_mut().resource_mut::<PumpConfig>().per_task_quota = Some(1);
+
+        assert_eq!(drain_update(&mut app), ["chatty", "quiet"]);
+        assert_eq!(drain_update(&mut app), ["chatty"]);
+        assert_eq!(drain_update(&mut app), ["chatty"]);
+        assert_eq!(queued_len(&app), 0);
+    }
+
+    #[test]
+    fn callbacks_wait_for_their_tickets_without_being_dropped() {
+        let mut app = app();
+        let task_channels = app.world().resource::<TaskChannels>().clone();
+        let ticket = task_channels.issue_ticket();
+        queue(
+            &app,
+            QueuedCallback {
+                after: vec![ticket],
+                ..QueuedCallback::new(record("after"))
+            },
+        );
+
+        assert!(drain_update(&mut app).is_empty());
+        assert!(drain_update(&mut app).is_empty());
+        assert_eq!(queued_len(&app), 1);
+
+        // The callback holding the ticket is queued behind the one waiting on it, but still runs
+        // first, and the waiting one follows in the same frame.
+        queue(
+            &app,
+            QueuedCallback {
+                ticket: Some(ticket),
+                ..QueuedCallback::new(record("before"))
+            },
+        );
+        assert_eq!(drain_update(&mut app), ["before", "after"]);
+        assert_eq!(queued_len(&app), 0);
+    }
+
+    #[test]
+    fn callbacks_are_held_until_their_tick() {
+        let mut app = app();
+        let ticks = app.world().resource::<UpdateTicks>().ticks();
+        let tick = ticks.load(std::sync::atomic::Ordering::SeqCst) + 2;
+        queue(
+            &app,
+            QueuedCallback {
+                run_at_tick: Some(tick),
+                ..QueuedCallback::new(record("at tick"))
+            },
+        );
+
+        assert!(drain_update(&mut app).is_empty());
+        ticks.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        assert!(drain_update(&mut app).is_empty());
+        ticks.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(drain_update(&mut app), ["at tick"]);
+    }
+
+    #[test]
+    fn coalescing_keeps_only_the_latest_callback() {
+        let mut app = app();
+        let task_channels = app.world().resource::<TaskChannels>().clone();
+        for label in ["first", "second", "latest"] {
+            queue(
+                &app,
+                QueuedCallback {
+                    coalesce: Some(task_channels.coalesce("position".into())),
+                    ..QueuedCallback::new(record(label))
+                },
+            );
+        }
+        queue(&app, QueuedCallback::new(record("other")));
+
+        assert_eq!(drain_update(&mut app), ["latest", "other"]);
+        assert_eq!(queued_len(&app), 0);
+    }
+
+    #[test]
+    fn dropping_a_repeat_handle_stops_rescheduling() {
+        let mut app = app();
+        let task_context = app.world().resource::<TaskContext>().clone();
+        let handle = task_context.submit_repeating(RepeatBy::Frames(1), |ctx| {
+            ctx.world.resource_mut::<Counter>().0 += 1;
+        });
+
+        // The first update schedules the callback, which then runs once in every later update.
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(app.world().resource::<Counter>().0, 2);
+
+        drop(handle);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert_eq!(app.world().resource::<Counter>().0, 2);
+        assert!(app.world().resource::<MainThreadTimers>().is_empty());
+    }
+}
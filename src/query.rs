@@ -0,0 +1,60 @@
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    query::{ReadOnlyQueryData, WorldQuery},
+};
+
+/// Query data whose items can be copied out of the world, so that
+/// [`TaskContext::query`](crate::TaskContext::query) can hand owned rows to a task. Implemented for
+/// [`Entity`], `&C` and `Option<&C>` for cloneable components, and tuples of those.
+pub trait OwnedQueryData: ReadOnlyQueryData {
+    type Owned: Send + 'static;
+
+    fn into_owned(item: <Self as WorldQuery>::Item<'_>) -> Self::Owned;
+}
+
+impl OwnedQueryData for Entity {
+    type Owned = Entity;
+
+    fn into_owned(entity: Entity) -> Entity {
+        entity
+    }
+}
+
+impl<C: Component + Clone> OwnedQueryData for &C {
+    type Owned = C;
+
+    fn into_owned(component: &C) -> C {
+        component.clone()
+    }
+}
+
+impl<C: Component + Clone> OwnedQueryData for Option<&C> {
+    type Owned = Option<C>;
+
+    fn into_owned(component: Option<&C>) -> Option<C> {
+        component.cloned()
+    }
+}
+
+macro_rules! impl_owned_query_data_tuple {
+    ($($name:ident),*) => {
+        impl<$($name: OwnedQueryData),*> OwnedQueryData for ($($name,)*) {
+            type Owned = ($($name::Owned,)*);
+
+            #[allow(non_snake_case)]
+            fn into_owned(($($name,)*): <Self as WorldQuery>::Item<'_>) -> Self::Owned {
+                ($($name::into_owned($name),)*)
+            }
+        }
+    };
+}
+
+impl_owned_query_data_tuple!(A);
+impl_owned_query_data_tuple!(A, B);
+impl_owned_query_data_tuple!(A, B, C);
+impl_owned_query_data_tuple!(A, B, C, D);
+impl_owned_query_data_tuple!(A, B, C, D, E);
+impl_owned_query_data_tuple!(A, B, C, D, E, F);
+impl_owned_query_data_tuple!(A, B, C, D, E, F, G);
+impl_owned_query_data_tuple!(A, B, C, D, E, F, G, H);
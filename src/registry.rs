@@ -0,0 +1,173 @@
+use crate::{task_id::TaskId, ticks::UpdateTicks, timing::TaskTimings};
+use bevy_ecs::system::{Res, Resource};
+use dashmap::DashMap;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// How many frames the [`TaskRegistry`] keeps the entries of stopped tasks for by default.
+pub const DEFAULT_FINISHED_TASK_RETENTION: usize = 300;
+
+/// The lifecycle state of a task in the [`TaskRegistry`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TaskStatus {
+    Running,
+    /// The task's future ran to completion.
+    Completed,
+    /// The task was dropped before completing, either because it was aborted or because its runtime
+    /// shut down.
+    Aborted,
+    /// The task panicked.
+    Panicked,
+}
+
+/// What the [`TaskRegistry`] knows about a single task.
+#[derive(Clone, Debug)]
+pub struct TaskInfo {
+    pub id: TaskId,
+    pub name: Option<Arc<str>>,
+    /// The main thread update count when the task was spawned.
+    pub spawn_tick: usize,
+    /// The main thread update count when the task stopped running, if it has.
+    pub stopped_tick: Option<usize>,
+    pub status: TaskStatus,
+}
+
+/// Every task spawned through [`Tasks`](crate::Tasks), along with its status, so that debug UIs and
+/// tests can enumerate what is running in the background. Entries for tasks which have stopped
+/// running are dropped after [`DEFAULT_FINISHED_TASK_RETENTION`] frames, or however long was set with
+/// [`TasksPlugin::with_finished_task_retention`](crate::TasksPlugin::with_finished_task_retention),
+/// along with their [`TaskTimings`].
+#[derive(Resource, Clone)]
+pub struct TaskRegistry {
+    tasks: Arc<DashMap<TaskId, TaskInfo>>,
+    retention: Option<usize>,
+}
+
+impl Default for TaskRegistry {
+    fn default() -> Self {
+        Self::with_retention(Some(DEFAULT_FINISHED_TASK_RETENTION))
+    }
+}
+
+impl TaskRegistry {
+    /// Creates a registry which drops the entries of stopped tasks after `retention` frames, or
+    /// keeps them until [`clear_finished`](Self::clear_finished) is called if `None`.
+    pub fn with_retention(retention: Option<usize>) -> Self {
+        Self {
+            tasks: Default::default(),
+            retention,
+        }
+    }
+
+    pub fn get(&self, id: TaskId) -> Option<TaskInfo> {
+        self.tasks.get(&id).map(|info| info.value().clone())
+    }
+
+    pub fn status(&self, id: TaskId) -> Option<TaskStatus> {
+        self.tasks.get(&id).map(|info| info.status)
+    }
+
+    /// Returns every registered task, ordered by id.
+    pub fn snapshot(&self) -> Vec<TaskInfo> {
+        let mut tasks: Vec<_> = self
+            .tasks
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        tasks.sort_by_key(|info| info.id);
+        tasks
+    }
+
+    /// Returns the tasks which are still running, ordered by id.
+    pub fn running(&self) -> Vec<TaskInfo> {
+        let mut tasks = self.snapshot();
+        tasks.retain(|info| info.status == TaskStatus::Running);
+        tasks
+    }
+
+    /// Drops the entries of every task which is no longer running, including those which panicked.
+    pub fn clear_finished(&self) {
+        self.tasks
+            .retain(|_, info| info.status == TaskStatus::Running);
+    }
+
+    /// Registers a task as running, returning a guard which records how it stopped.
+    pub(crate) fn register(
+        &self,
+        id: TaskId,
+        name: Option<Arc<str>>,
+        ticks: Arc<AtomicUsize>,
+    ) -> Registration {
+        self.tasks.insert(
+            id,
+            TaskInfo {
+                id,
+                name,
+                spawn_tick: ticks.load(Ordering::SeqCst),
+                stopped_tick: None,
+                status: TaskStatus::Running,
+            },
+        );
+        Registration {
+            registry: self.clone(),
+            id,
+            ticks,
+            outcome: TaskStatus::Aborted,
+        }
+    }
+
+    fn set_stopped(&self, id: TaskId, status: TaskStatus, tick: usize) {
+        if let Some(mut info) = self.tasks.get_mut(&id) {
+            info.status = status;
+            info.stopped_tick = Some(tick);
+        }
+    }
+
+    /// Drops the entries of tasks which stopped more than the retention period ago, and the timings
+    /// of tasks which are no longer registered.
+    pub(crate) fn prune_system(
+        registry: Res<Self>,
+        ticks: Res<UpdateTicks>,
+        timings: Option<Res<TaskTimings>>,
+    ) {
+        let Some(retention) = registry.retention else {
+            return;
+        };
+        let tick = ticks.tick();
+        registry.tasks.retain(|_, info| {
+            info.stopped_tick
+                .map_or(true, |stopped| tick.wrapping_sub(stopped) < retention)
+        });
+        if let Some(timings) = timings {
+            timings.retain_finished(|id| registry.tasks.contains_key(&id));
+        }
+    }
+}
+
+/// Records how a task stopped when dropped, which is [`Aborted`](TaskStatus::Aborted) unless another
+/// outcome was recorded first.
+pub(crate) struct Registration {
+    registry: TaskRegistry,
+    id: TaskId,
+    ticks: Arc<AtomicUsize>,
+    outcome: TaskStatus,
+}
+
+impl Registration {
+    pub(crate) fn complete(&mut self) {
+        self.outcome = TaskStatus::Completed;
+    }
+
+    pub(crate) fn panicked(&mut self) {
+        self.outcome = TaskStatus::Panicked;
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.registry
+            .set_stopped(self.id, self.outcome, self.ticks.load(Ordering::SeqCst));
+    }
+}
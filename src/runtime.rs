@@ -1,23 +1,242 @@
 use bevy_ecs::system::Resource;
+#[cfg(feature = "tokio")]
+use bevy_ecs::system::{Commands, ResMut};
+#[cfg(feature = "tokio")]
+use bevy_utils::tracing::warn;
+use bevy_utils::HashMap;
 
 #[cfg(feature = "tokio")]
-use std::{future::Future, sync::Arc};
+use std::future::Future;
+#[cfg(any(feature = "tokio", feature = "smol"))]
+use std::sync::{Arc, OnceLock};
 
+/// The Tokio runtime that tasks are spawned onto. This is either a runtime owned by the plugin, or a
+/// [`Handle`](tokio::runtime::Handle) to a runtime owned by someone else, such as a server
+/// framework which was started before the app.
 #[cfg(feature = "tokio")]
-#[derive(Resource)]
-pub struct Runtime(pub Arc<tokio::runtime::Runtime>);
+#[derive(Resource, Clone)]
+pub enum Runtime {
+    Owned(Arc<tokio::runtime::Runtime>),
+    Handle(tokio::runtime::Handle),
+}
 
 #[cfg(feature = "tokio")]
 impl Default for Runtime {
     fn default() -> Self {
+        Self::from_builder(Self::default_builder())
+    }
+}
+
+/// Watches whether the Tokio [`Runtime`] is still running. A probe task is parked on the runtime, and
+/// is dropped along with every other task if the runtime shuts down, which is checked at the start of
+/// every frame. If recovery was enabled with
+/// [`TasksPlugin::with_runtime_recovery`](crate::TasksPlugin::with_runtime_recovery), a stopped
+/// runtime is replaced with a freshly built one.
+#[cfg(feature = "tokio")]
+#[derive(Resource)]
+pub struct RuntimeHealth {
+    probe_rx: tokio::sync::oneshot::Receiver<()>,
+    alive: bool,
+    recover: Option<Arc<dyn Fn() -> Runtime + Send + Sync + 'static>>,
+}
+
+#[cfg(feature = "tokio")]
+impl RuntimeHealth {
+    pub(crate) fn new(
+        runtime: &Runtime,
+        recover: Option<Arc<dyn Fn() -> Runtime + Send + Sync + 'static>>,
+    ) -> Self {
+        Self {
+            probe_rx: Self::probe(runtime),
+            alive: true,
+            recover,
+        }
+    }
+
+    fn probe(runtime: &Runtime) -> tokio::sync::oneshot::Receiver<()> {
+        let (probe_tx, probe_rx) = tokio::sync::oneshot::channel();
+        runtime.handle().spawn(async move {
+            let _probe_tx = probe_tx;
+            std::future::pending::<()>().await
+        });
+        probe_rx
+    }
+
+    /// Whether the runtime was still running as of the start of this frame.
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+
+    pub(crate) fn check_system(mut health: ResMut<Self>, mut commands: Commands) {
+        let stopped = matches!(
+            health.probe_rx.try_recv(),
+            Err(tokio::sync::oneshot::error::TryRecvError::Closed)
+        );
+        if !stopped || !health.alive {
+            return;
+        }
+        match health.recover.clone() {
+            Some(recover) => {
+                warn!("The Tokio runtime for background tasks stopped running; rebuilding it");
+                let runtime = recover();
+                health.probe_rx = Self::probe(&runtime);
+                commands.insert_resource(runtime);
+            }
+            None => {
+                warn!("The Tokio runtime for background tasks stopped running; tasks can no longer be spawned");
+                health.alive = false;
+            }
+        }
+    }
+}
+
+/// Operating system scheduling settings applied to every thread owned by the Tokio runtime, both the
+/// async worker threads and the blocking pool threads.
+#[cfg(feature = "thread-priority")]
+#[derive(Clone, Debug, Default)]
+pub struct ThreadConfig {
+    /// The priority to give runtime threads. Lowering this below the main thread's priority keeps
+    /// background work from competing with rendering and the Bevy schedule.
+    pub priority: Option<thread_priority::ThreadPriority>,
+    /// Indices of the CPU cores runtime threads may be pinned to. Threads are assigned to these cores
+    /// round-robin as they start. `None` means all cores.
+    pub cores: Option<Vec<usize>>,
+    /// Indices of the CPU cores runtime threads must never be pinned to, for example to keep the
+    /// core running the main thread free.
+    pub excluded_cores: Vec<usize>,
+}
+
+#[cfg(feature = "thread-priority")]
+impl ThreadConfig {
+    pub fn with_priority(mut self, priority: thread_priority::ThreadPriority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    pub fn with_cores(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.cores = Some(cores.into_iter().collect());
+        self
+    }
+
+    pub fn excluding_cores(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.excluded_cores.extend(cores);
+        self
+    }
+
+    /// Resolves the configured core indices against the cores actually present on this machine.
+    fn core_ids(&self) -> Vec<core_affinity::CoreId> {
+        if self.cores.is_none() && self.excluded_cores.is_empty() {
+            return Vec::new();
+        }
+        core_affinity::get_core_ids()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|core| {
+                self.cores
+                    .as_ref()
+                    .map_or(true, |cores| cores.contains(&core.id))
+            })
+            .filter(|core| !self.excluded_cores.contains(&core.id))
+            .collect()
+    }
+
+    fn apply(&self, builder: &mut tokio::runtime::Builder) {
+        let priority = self.priority;
+        let core_ids = self.core_ids();
+        let next_core = std::sync::atomic::AtomicUsize::new(0);
+        builder.on_thread_start(move || {
+            if let Some(priority) = priority {
+                // Raising or lowering priority can be refused by the OS (e.g. without privileges),
+                // in which case the thread simply keeps its default priority.
+                let _ = thread_priority::set_current_thread_priority(priority);
+            }
+            if !core_ids.is_empty() {
+                let index = next_core.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                core_affinity::set_for_current(core_ids[index % core_ids.len()]);
+            }
+        });
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Runtime {
+    /// The Tokio runtime builder used by [`Runtime::default`]. On the wasm32 architecture this is the
+    /// current-thread runtime, on all other architectures it's the multi-thread runtime.
+    pub fn default_builder() -> tokio::runtime::Builder {
         #[cfg(not(target_arch = "wasm32"))]
         let mut runtime = tokio::runtime::Builder::new_multi_thread();
         #[cfg(target_arch = "wasm32")]
         let mut runtime = tokio::runtime::Builder::new_current_thread();
         runtime.enable_all();
-        Self(Arc::new(runtime.build().expect(
-            "Failed to create Tokio runtime for background tasks",
-        )))
+        runtime
+    }
+
+    /// The name under which [`TasksPlugin::with_compute_and_io_runtimes`](crate::TasksPlugin::with_compute_and_io_runtimes)
+    /// registers the compute runtime.
+    pub const COMPUTE: &'static str = "compute";
+    /// The name under which [`TasksPlugin::with_compute_and_io_runtimes`](crate::TasksPlugin::with_compute_and_io_runtimes)
+    /// registers the IO runtime.
+    pub const IO: &'static str = "io";
+
+    /// A runtime builder for CPU-heavy work: one worker thread per core, each with a large stack for
+    /// deeply recursive jobs such as pathfinding or procedural generation.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn compute_builder() -> tokio::runtime::Builder {
+        let mut runtime = tokio::runtime::Builder::new_multi_thread();
+        runtime
+            .enable_all()
+            .thread_name("bevy-tasks-compute")
+            .thread_stack_size(8 * 1024 * 1024)
+            .worker_threads(available_cores());
+        runtime
+    }
+
+    /// A runtime builder for IO-bound work: more worker threads than cores, with a larger blocking
+    /// pool for synchronous file access, so slow sockets and disks don't starve each other.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn io_builder() -> tokio::runtime::Builder {
+        let mut runtime = tokio::runtime::Builder::new_multi_thread();
+        runtime
+            .enable_all()
+            .thread_name("bevy-tasks-io")
+            .worker_threads(available_cores() * 2)
+            .max_blocking_threads(1024);
+        runtime
+    }
+
+    /// Builds a runtime from [`compute_builder`](Self::compute_builder).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn compute() -> Self {
+        Self::from_builder(Self::compute_builder())
+    }
+
+    /// Builds a runtime from [`io_builder`](Self::io_builder).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn io() -> Self {
+        Self::from_builder(Self::io_builder())
+    }
+
+    pub fn from_builder(mut builder: tokio::runtime::Builder) -> Self {
+        Self::Owned(Arc::new(
+            builder
+                .build()
+                .expect("Failed to create Tokio runtime for background tasks"),
+        ))
+    }
+
+    /// Wraps a handle to an existing runtime, so that tasks are spawned onto it instead of a runtime
+    /// owned by the plugin.
+    pub fn from_handle(handle: tokio::runtime::Handle) -> Self {
+        Self::Handle(handle)
+    }
+
+    /// Builds the default runtime, applying the given thread priority and affinity settings to
+    /// each of its threads.
+    #[cfg(feature = "thread-priority")]
+    pub fn with_thread_config(config: &ThreadConfig) -> Self {
+        let mut builder = Self::default_builder();
+        config.apply(&mut builder);
+        Self::from_builder(builder)
     }
 }
 
@@ -25,19 +244,158 @@ impl Default for Runtime {
 #[derive(Resource, Default)]
 pub struct Runtime;
 
+#[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+fn available_cores() -> usize {
+    std::thread::available_parallelism().map_or(1, |cores| cores.get())
+}
+
+/// Additional runtimes registered with [`TasksPlugin::add_runtime`](crate::TasksPlugin::add_runtime),
+/// keyed by name, so that e.g. network IO and heavy compute can run on separately sized thread pools.
+#[derive(Resource, Default)]
+pub struct NamedRuntimes {
+    runtimes: HashMap<String, Runtime>,
+}
+
+impl NamedRuntimes {
+    pub fn get(&self, name: &str) -> Option<&Runtime> {
+        self.runtimes.get(name)
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, runtime: Runtime) {
+        self.runtimes.insert(name.into(), runtime);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.runtimes.keys().map(String::as_str)
+    }
+
+    /// Replaces each runtime with the result of `f`, removing those for which it returns `None`.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn filter_map(&mut self, mut f: impl FnMut(Runtime) -> Option<Runtime>) {
+        self.runtimes = std::mem::take(&mut self.runtimes)
+            .into_iter()
+            .filter_map(|(name, runtime)| Some((name, f(runtime)?)))
+            .collect();
+    }
+}
+
+/// A dedicated thread running a Tokio [`LocalSet`](tokio::task::LocalSet), onto which tasks whose
+/// futures aren't `Send` are spawned by [`Tasks::spawn_local`](crate::Tasks::spawn_local). The
+/// thread is only started once the first task is spawned onto it.
+#[cfg(feature = "tokio")]
+#[derive(Resource, Clone, Default)]
+pub struct LocalRuntime {
+    pool: Arc<OnceLock<tokio_util::task::LocalPoolHandle>>,
+}
+
+#[cfg(feature = "tokio")]
+impl LocalRuntime {
+    /// The pool backing this runtime, starting its thread if it hasn't been already.
+    pub fn pool(&self) -> &tokio_util::task::LocalPoolHandle {
+        self.pool
+            .get_or_init(|| tokio_util::task::LocalPoolHandle::new(1))
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+#[derive(Resource, Clone, Default)]
+pub struct LocalRuntime;
+
+/// A [`smol::Executor`] driven by a small pool of threads, used by
+/// [`Tasks::spawn_smol`](crate::Tasks::spawn_smol). The tick channel and main thread callbacks don't
+/// depend on Tokio's runtime, so tasks on this executor get the full [`TaskContext`](crate::TaskContext).
+/// The threads are only started once the first task is spawned onto the executor, and exit once the
+/// last clone of this resource is dropped.
+#[cfg(feature = "smol")]
+#[derive(Resource, Clone)]
+pub struct SmolRuntime {
+    threads: usize,
+    workers: Arc<OnceLock<SmolWorkers>>,
+}
+
+#[cfg(feature = "smol")]
+struct SmolWorkers {
+    executor: Arc<smol::Executor<'static>>,
+    _shutdown_tx: smol::channel::Sender<()>,
+}
+
+#[cfg(feature = "smol")]
+impl SmolRuntime {
+    pub fn new(threads: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            workers: Default::default(),
+        }
+    }
+
+    /// The executor, starting its threads if they haven't been already.
+    pub fn executor(&self) -> &Arc<smol::Executor<'static>> {
+        &self.workers.get_or_init(|| self.start()).executor
+    }
+
+    fn start(&self) -> SmolWorkers {
+        let executor = Arc::new(smol::Executor::new());
+        let (shutdown_tx, shutdown_rx) = smol::channel::bounded::<()>(1);
+        for index in 0..self.threads {
+            let executor = executor.clone();
+            let shutdown_rx = shutdown_rx.clone();
+            std::thread::Builder::new()
+                .name(format!("smol-worker-{index}"))
+                .spawn(move || smol::block_on(executor.run(shutdown_rx.recv())))
+                .expect("Failed to spawn smol executor thread");
+        }
+        SmolWorkers {
+            executor,
+            _shutdown_tx: shutdown_tx,
+        }
+    }
+}
+
+#[cfg(feature = "smol")]
+impl Default for SmolRuntime {
+    fn default() -> Self {
+        Self::new(std::thread::available_parallelism().map_or(1, |threads| threads.get()))
+    }
+}
+
+#[cfg(not(feature = "smol"))]
+#[derive(Resource, Clone, Default)]
+pub struct SmolRuntime;
+
 impl Runtime {
+    /// The runtime owned by the plugin, or `None` when wrapping a handle to an external runtime.
+    #[cfg(feature = "tokio")]
+    pub fn raw(&self) -> Option<&tokio::runtime::Runtime> {
+        match self {
+            Self::Owned(runtime) => Some(runtime),
+            Self::Handle(_) => None,
+        }
+    }
+
     #[cfg(feature = "tokio")]
-    pub fn raw(&self) -> &tokio::runtime::Runtime {
-        &self.0
+    pub fn runtime_arc(&self) -> Option<Arc<tokio::runtime::Runtime>> {
+        match self {
+            Self::Owned(runtime) => Some(runtime.clone()),
+            Self::Handle(_) => None,
+        }
     }
 
+    /// A handle to the runtime, whichever kind it is.
     #[cfg(feature = "tokio")]
-    pub fn runtime_arc(&self) -> Arc<tokio::runtime::Runtime> {
-        self.0.clone()
+    pub fn handle(&self) -> &tokio::runtime::Handle {
+        match self {
+            Self::Owned(runtime) => runtime.handle(),
+            Self::Handle(handle) => handle,
+        }
     }
 
+    /// Blocks on `future`. An owned runtime drives it directly, so this also works with a
+    /// current-thread runtime, whose IO and timer drivers a bare handle can't drive.
     #[cfg(feature = "tokio")]
     pub fn block_on<F: Future>(&self, future: F) -> F::Output {
-        self.0.block_on(future)
+        match self {
+            Self::Owned(runtime) => runtime.block_on(future),
+            Self::Handle(handle) => handle.block_on(future),
+        }
     }
 }
@@ -0,0 +1,103 @@
+#[cfg(feature = "tokio")]
+use crate::runtime::{NamedRuntimes, Runtime};
+use crate::{app_exit::AppExitSignal, pump, task_channels::TaskChannels, tracking::TrackedTasks};
+use bevy_ecs::{
+    system::{Local, Resource},
+    world::World,
+};
+use bevy_utils::{Duration, Instant};
+
+/// What happens to background tasks which are still running when the app exits.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExitPolicy {
+    /// Leave running tasks alone, so they're dropped along with the runtime.
+    #[default]
+    Detach,
+    /// Abort every tracked task as soon as the app exits, then run any main thread callbacks which
+    /// were already queued.
+    Abort,
+    /// Keep running main thread callbacks while waiting up to the given duration for tracked tasks to
+    /// finish, then abort whatever is left. The main thread can't block on wasm, so there callbacks are
+    /// only flushed once before aborting.
+    Drain(Duration),
+}
+
+/// How long to wait for the runtime's worker threads to finish when shutting it down on exit,
+/// configured with [`TasksPlugin::with_shutdown_timeout`](crate::TasksPlugin::with_shutdown_timeout).
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ShutdownTimeout(pub Duration);
+
+/// Shuts down the runtime, waiting at most `timeout` for its threads. Runtimes which aren't owned
+/// solely by the plugin, including handles to external runtimes, are handed back untouched.
+#[cfg(feature = "tokio")]
+fn shutdown_runtime(runtime: Runtime, timeout: Duration) -> Option<Runtime> {
+    match runtime {
+        Runtime::Owned(runtime) => match std::sync::Arc::try_unwrap(runtime) {
+            Ok(runtime) => {
+                runtime.shutdown_timeout(timeout);
+                None
+            }
+            Err(runtime) => Some(Runtime::Owned(runtime)),
+        },
+        Runtime::Handle(handle) => Some(Runtime::Handle(handle)),
+    }
+}
+
+/// Runs every main thread callback currently queued, in every schedule.
+fn flush_callbacks(world: &mut World) {
+    let schedules: Vec<_> = world
+        .resource::<TaskChannels>()
+        .queue_lengths()
+        .into_iter()
+        .map(|(schedule, _)| schedule)
+        .collect();
+    for schedule in schedules {
+        pump::drain(world, schedule);
+    }
+}
+
+/// Applies the [`ExitPolicy`] once the app has seen an [`AppExit`](bevy_app::AppExit) event.
+pub(crate) fn on_app_exit_system(world: &mut World, mut handled: Local<bool>) {
+    if *handled || world.resource::<AppExitSignal>().exit().is_none() {
+        return;
+    }
+    *handled = true;
+
+    let policy = world
+        .get_resource::<ExitPolicy>()
+        .copied()
+        .unwrap_or_default();
+    match policy {
+        ExitPolicy::Detach => {}
+        ExitPolicy::Abort => {
+            world.resource::<TrackedTasks>().abort_all();
+            flush_callbacks(world);
+        }
+        ExitPolicy::Drain(timeout) => {
+            let deadline = Instant::now() + timeout;
+            loop {
+                flush_callbacks(world);
+                if cfg!(target_arch = "wasm32")
+                    || world.resource::<TrackedTasks>().is_empty()
+                    || Instant::now() >= deadline
+                {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            world.resource::<TrackedTasks>().abort_all();
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    if let Some(ShutdownTimeout(timeout)) = world.get_resource::<ShutdownTimeout>().copied() {
+        if let Some(runtime) = world.remove_resource::<Runtime>() {
+            if let Some(runtime) = shutdown_runtime(runtime, timeout) {
+                world.insert_resource(runtime);
+            }
+        }
+        if let Some(mut named) = world.get_resource_mut::<NamedRuntimes>() {
+            named.filter_map(|runtime| shutdown_runtime(runtime, timeout));
+        }
+    }
+}
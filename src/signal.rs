@@ -0,0 +1,102 @@
+use crate::Tasks;
+use bevy_app::{App, AppExit, Plugin, Startup};
+use bevy_ecs::event::Event;
+
+/// The OS signal which caused a [`ShutdownRequested`] event.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ShutdownSignal {
+    /// SIGINT on unix, Ctrl-C elsewhere.
+    Interrupt,
+    /// SIGTERM, only received on unix.
+    Terminate,
+}
+
+/// Sent when the process receives a shutdown signal.
+#[derive(Event, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ShutdownRequested {
+    pub signal: ShutdownSignal,
+}
+
+/// Spawns a background task listening for SIGINT and SIGTERM (Ctrl-C on non-unix platforms) which
+/// emits a [`ShutdownRequested`] event for each signal received, and by default also sends
+/// [`AppExit`] so that headless servers shut down cleanly under orchestration. Requires the
+/// [`TasksPlugin`](crate::TasksPlugin) to be added first.
+pub struct SignalPlugin {
+    /// Whether to send [`AppExit::Success`] alongside each [`ShutdownRequested`] event.
+    pub exit_app: bool,
+}
+
+impl Default for SignalPlugin {
+    fn default() -> Self {
+        Self { exit_app: true }
+    }
+}
+
+impl SignalPlugin {
+    fn spawn_listener(tasks: &Tasks, exit_app: bool) {
+        tasks.spawn_tokio(move |ctx| async move {
+            let Ok(mut signals) = Signals::new() else {
+                return;
+            };
+            while let Some(signal) = signals.recv().await {
+                ctx.submit_on_main_thread(move |ctx| {
+                    ctx.world.send_event(ShutdownRequested { signal });
+                    if exit_app {
+                        ctx.world.send_event(AppExit::Success);
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl Plugin for SignalPlugin {
+    fn build(&self, app: &mut App) {
+        let exit_app = self.exit_app;
+        app.add_event::<ShutdownRequested>()
+            .add_systems(Startup, move |tasks: Tasks| {
+                Self::spawn_listener(&tasks, exit_app)
+            });
+    }
+}
+
+#[cfg(unix)]
+struct Signals {
+    interrupt: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    fn new() -> std::io::Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+        })
+    }
+
+    async fn recv(&mut self) -> Option<ShutdownSignal> {
+        tokio::select! {
+            received = self.interrupt.recv() => received.map(|()| ShutdownSignal::Interrupt),
+            received = self.terminate.recv() => received.map(|()| ShutdownSignal::Terminate),
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    fn new() -> std::io::Result<Self> {
+        Ok(Self)
+    }
+
+    async fn recv(&mut self) -> Option<ShutdownSignal> {
+        tokio::signal::ctrl_c()
+            .await
+            .ok()
+            .map(|()| ShutdownSignal::Interrupt)
+    }
+}
@@ -1,50 +1,509 @@
-use crate::context::main_thread::{MainThreadCallback, MainThreadContext};
-use bevy_ecs::{schedule::InternedScheduleLabel, system::Resource};
-use dashmap::DashMap;
-use std::sync::Arc;
+use crate::context::main_thread::{
+    DefaultCallbackSchedule, MainThreadCallback, MainThreadContext, ReadOnlyCallback,
+};
+use crate::task_id::TaskId;
+use bevy_app::Update;
+use bevy_ecs::{
+    schedule::{InternedScheduleLabel, ScheduleLabel},
+    system::Resource,
+    world::World,
+};
+use bevy_utils::{Duration, Instant};
+use crossbeam_queue::SegQueue;
+use dashmap::{DashMap, DashSet};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 #[derive(Resource, Clone, Default)]
 pub struct TaskChannels {
-    channels: Arc<DashMap<InternedScheduleLabel, ChannelPair>>,
+    /// The queues of the schedules known when the plugin was built, which are found without
+    /// touching any lock. There are only a handful, so a linear scan beats hashing.
+    registered: Arc<[(InternedScheduleLabel, Arc<ScheduleQueue>)]>,
+    /// The queues of any other schedules, created when they're first submitted to.
+    channels: Arc<DashMap<InternedScheduleLabel, Arc<ScheduleQueue>>>,
+    /// Tickets which have been issued but whose callbacks haven't run yet.
+    pending_tickets: Arc<DashSet<CallbackTicket>>,
+    /// The newest generation submitted for each coalescing key whose callback hasn't run yet.
+    coalesce_keys: Arc<DashMap<Arc<str>, u64>>,
+    /// Permits for queued callbacks, if the queues are bounded. Each queued callback holds a permit
+    /// until it runs or is discarded.
+    capacity: Option<Arc<Semaphore>>,
+    /// The schedule which [`DefaultCallbackSchedule`] stands for, if not [`Update`].
+    default_schedule: Option<InternedScheduleLabel>,
 }
 
-struct ChannelPair {
-    task_tx: tokio::sync::mpsc::UnboundedSender<MainThreadCallback>,
-    task_rx: tokio::sync::mpsc::UnboundedReceiver<MainThreadCallback>,
+/// An opaque handle to a submitted main thread callback, which later submissions (possibly from other
+/// tasks) can declare they must run [`after`](crate::MainThreadRunConfiguration::after).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CallbackTicket(u64);
+
+/// How urgently a main thread callback should run relative to the others queued in its schedule.
+/// When several lanes have callbacks waiting, the pump runs roughly four high priority callbacks
+/// and two normal ones for every low priority one, so bulk work like telemetry can't delay
+/// gameplay-affecting callbacks but still makes progress.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    pub(crate) const ALL: [Self; 3] = [Self::High, Self::Normal, Self::Low];
+
+    pub(crate) fn lane(self) -> usize {
+        self as usize
+    }
+}
+
+/// The order the lanes are served in, which gives them a 4:2:1 weighting.
+pub(crate) const LANE_TURNS: [Priority; 7] = [
+    Priority::High,
+    Priority::Normal,
+    Priority::High,
+    Priority::Low,
+    Priority::High,
+    Priority::Normal,
+    Priority::High,
+];
+
+/// A main thread callback waiting in a schedule's queue, along with the task which submitted it.
+pub struct QueuedCallback {
+    pub task: Option<TaskId>,
+    pub priority: Priority,
+    /// The ticket which is completed once this callback runs.
+    pub ticket: Option<CallbackTicket>,
+    /// Tickets which must be completed before this callback may run.
+    pub after: Vec<CallbackTicket>,
+    /// The update tick before which the pump holds the callback back.
+    pub run_at_tick: Option<usize>,
+    /// Set if the callback should be skipped when a newer callback with the same key is queued.
+    pub coalesce: Option<Coalesce>,
+    /// When the callback was submitted.
+    pub queued_at: Instant,
+    pub callback: MainThreadCallback,
+}
+
+/// Identifies a queued callback among those submitted with the same coalescing key, created by
+/// [`TaskChannels::coalesce`].
+pub struct Coalesce {
+    key: Arc<str>,
+    generation: u64,
 }
 
-impl Default for ChannelPair {
-    fn default() -> Self {
-        let (task_tx, task_rx) = tokio::sync::mpsc::unbounded_channel();
-        Self { task_tx, task_rx }
+impl QueuedCallback {
+    pub fn new(callback: MainThreadCallback) -> Self {
+        Self {
+            task: None,
+            priority: Priority::Normal,
+            ticket: None,
+            after: Vec::new(),
+            run_at_tick: None,
+            coalesce: None,
+            queued_at: Instant::now(),
+            callback,
+        }
+    }
+}
+
+/// A schedule's queue, made up of one lock-free lane per [`Priority`], which submitters push onto
+/// without contending with each other. Only the pump takes the lock on its own state.
+#[derive(Default)]
+struct ScheduleQueue {
+    lanes: [SegQueue<QueuedCallback>; 3],
+    readonly: SegQueue<ReadOnlyCallback>,
+    pump: Mutex<PumpState>,
+    /// When the oldest callback in `lanes` was submitted, as a [`Stamp`], or zero if they were
+    /// empty when last checked.
+    oldest_in_lanes: AtomicU64,
+}
+
+/// Nanoseconds since a process-wide epoch, plus one so that zero can mean "none".
+struct Stamp;
+
+impl Stamp {
+    fn epoch() -> Instant {
+        static EPOCH: OnceLock<Instant> = OnceLock::new();
+        *EPOCH.get_or_init(Instant::now)
+    }
+
+    fn from_instant(instant: Instant) -> u64 {
+        instant.saturating_duration_since(Self::epoch()).as_nanos() as u64 + 1
+    }
+
+    fn to_instant(stamp: u64) -> Option<Instant> {
+        let nanos = stamp.checked_sub(1)?;
+        Some(Self::epoch() + Duration::from_nanos(nanos))
+    }
+}
+
+#[derive(Default)]
+struct PumpState {
+    /// Callbacks which were held back by the pump in an earlier frame, per lane. These run before
+    /// anything newly submitted to the lane.
+    deferred: [VecDeque<QueuedCallback>; 3],
+    /// Which entry of [`LANE_TURNS`] is served next.
+    turn: usize,
+}
+
+impl ScheduleQueue {
+    fn pump(&self) -> MutexGuard<'_, PumpState> {
+        self.pump.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn push(&self, queued: QueuedCallback) {
+        let stamp = Stamp::from_instant(queued.queued_at);
+        self.lanes[queued.priority.lane()].push(queued);
+        let _ =
+            self.oldest_in_lanes
+                .compare_exchange(0, stamp, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    fn pop(&self) -> Option<QueuedCallback> {
+        let mut pump = self.pump();
+        let preferred = LANE_TURNS[pump.turn % LANE_TURNS.len()];
+        pump.turn = pump.turn.wrapping_add(1);
+        let queued = std::iter::once(preferred)
+            .chain(Priority::ALL)
+            .map(Priority::lane)
+            .find_map(|lane| {
+                pump.deferred[lane]
+                    .pop_front()
+                    .or_else(|| self.lanes[lane].pop())
+            });
+        if self.lanes.iter().all(SegQueue::is_empty) {
+            self.oldest_in_lanes.store(0, Ordering::Relaxed);
+            // A callback pushed while the lanes were being emptied may have lost its stamp, so it
+            // counts from now instead.
+            if !self.lanes.iter().all(SegQueue::is_empty) {
+                let _ = self.oldest_in_lanes.compare_exchange(
+                    0,
+                    Stamp::from_instant(Instant::now()),
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                );
+            }
+        }
+        queued
+    }
+
+    fn oldest_queued_at(&self) -> Option<Instant> {
+        let deferred = self
+            .pump()
+            .deferred
+            .iter()
+            .flatten()
+            .map(|queued| queued.queued_at)
+            .min();
+        let in_lanes = Stamp::to_instant(self.oldest_in_lanes.load(Ordering::Relaxed));
+        deferred.into_iter().chain(in_lanes).min()
+    }
+
+    fn len(&self) -> usize {
+        let deferred: usize = self.pump().deferred.iter().map(VecDeque::len).sum();
+        deferred + self.lanes.iter().map(SegQueue::len).sum::<usize>()
+    }
+
+    fn clear(&self) {
+        for deferred in &mut self.pump().deferred {
+            deferred.clear();
+        }
+        for lane in &self.lanes {
+            while lane.pop().is_some() {}
+        }
+        while self.readonly.pop().is_some() {}
     }
 }
 
 impl TaskChannels {
+    /// Creates channels which hold at most `capacity` queued callbacks across every schedule. Only
+    /// [`reserve`](Self::reserve) waits for capacity; other submissions are still queued when the
+    /// channels are full, but take up capacity when there's some free.
+    pub fn bounded(capacity: usize) -> Self {
+        Self {
+            capacity: Some(Arc::new(Semaphore::new(capacity))),
+            ..Default::default()
+        }
+    }
+
+    /// Waits until there's capacity for another callback, for channels created with
+    /// [`bounded`](Self::bounded). Returns immediately with `None` for unbounded channels.
+    pub async fn reserve(&self) -> Option<OwnedSemaphorePermit> {
+        self.capacity.clone()?.acquire_owned().await.ok()
+    }
+
+    /// Creates the queues for `schedules` up front, so that submitting to them never has to look
+    /// them up in a concurrent map.
+    pub fn with_schedules(
+        mut self,
+        schedules: impl IntoIterator<Item = InternedScheduleLabel>,
+    ) -> Self {
+        self.registered = schedules
+            .into_iter()
+            .map(|schedule| (schedule, Arc::default()))
+            .collect();
+        self
+    }
+
+    /// Sends callbacks submitted for [`DefaultCallbackSchedule`] to `schedule` instead of [`Update`].
+    pub fn with_default_schedule(mut self, schedule: InternedScheduleLabel) -> Self {
+        self.default_schedule = Some(schedule);
+        self
+    }
+
+    /// Returns the schedule that callbacks submitted for `schedule` actually run in.
+    pub fn resolve(&self, schedule: InternedScheduleLabel) -> InternedScheduleLabel {
+        if schedule == DefaultCallbackSchedule.intern() {
+            self.default_schedule.unwrap_or_else(|| Update.intern())
+        } else {
+            schedule
+        }
+    }
+
+    fn registered_queue(&self, schedule: InternedScheduleLabel) -> Option<&ScheduleQueue> {
+        self.registered
+            .iter()
+            .find(|(label, _)| *label == schedule)
+            .map(|(_, queue)| &**queue)
+    }
+
+    /// Runs `f` with the queue for `schedule`, creating it if needed.
+    fn with_queue<Output>(
+        &self,
+        schedule: InternedScheduleLabel,
+        f: impl FnOnce(&ScheduleQueue) -> Output,
+    ) -> Output {
+        let schedule = self.resolve(schedule);
+        match self.registered_queue(schedule) {
+            Some(queue) => f(queue),
+            None => f(&self.channels.entry(schedule).or_default()),
+        }
+    }
+
+    /// Runs `f` with the queue for `schedule`, if anything has been submitted to it.
+    fn with_existing_queue<Output>(
+        &self,
+        schedule: InternedScheduleLabel,
+        f: impl FnOnce(&ScheduleQueue) -> Output,
+    ) -> Option<Output> {
+        let schedule = self.resolve(schedule);
+        match self.registered_queue(schedule) {
+            Some(queue) => Some(f(queue)),
+            None => self.channels.get(&schedule).map(|queue| f(&queue)),
+        }
+    }
+
+    fn try_reserve(&self) -> Option<OwnedSemaphorePermit> {
+        self.capacity.clone()?.try_acquire_owned().ok()
+    }
+
     pub fn submit(
         &self,
         schedule: InternedScheduleLabel,
         callback: impl FnOnce(MainThreadContext) + Send + 'static,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        self.task_tx(schedule).send(Box::new(callback))?;
+        self.submit_from(schedule, None, callback)
+    }
+
+    /// Like [`submit`](Self::submit), but records which task the callback came from so that the
+    /// pump can apply per-task limits.
+    pub fn submit_from(
+        &self,
+        schedule: InternedScheduleLabel,
+        task: Option<TaskId>,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.submit_queued(
+            schedule,
+            QueuedCallback {
+                task,
+                ..QueuedCallback::new(Box::new(callback))
+            },
+        )
+    }
+
+    pub fn submit_queued(
+        &self,
+        schedule: InternedScheduleLabel,
+        queued: QueuedCallback,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.submit_reserved(schedule, queued, self.try_reserve())
+    }
+
+    /// Like [`submit_queued`](Self::submit_queued), using capacity obtained from
+    /// [`reserve`](Self::reserve). The permit is released once the callback runs or is discarded.
+    pub fn submit_reserved(
+        &self,
+        schedule: InternedScheduleLabel,
+        mut queued: QueuedCallback,
+        permit: Option<OwnedSemaphorePermit>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(permit) = permit {
+            let callback = queued.callback;
+            queued.callback = Box::new(move |ctx| {
+                let _permit = permit;
+                callback(ctx);
+            });
+        }
+        self.with_queue(schedule, |queue| queue.push(queued));
+        Ok(())
+    }
+
+    /// Queues a callback which only reads the world, to be run by the read-only pump for `schedule`.
+    pub fn submit_readonly(
+        &self,
+        schedule: InternedScheduleLabel,
+        callback: impl FnOnce(&World) + Send + 'static,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.with_queue(schedule, |queue| queue.readonly.push(Box::new(callback)));
         Ok(())
     }
 
-    pub fn task_tx(
+    pub fn try_recv_readonly(&self, schedule: InternedScheduleLabel) -> Option<ReadOnlyCallback> {
+        self.with_existing_queue(schedule, |queue| queue.readonly.pop())
+            .flatten()
+    }
+
+    /// Marks a callback about to be submitted as the newest one for `key`, superseding any callback
+    /// queued earlier with the same key.
+    pub fn coalesce(&self, key: Arc<str>) -> Coalesce {
+        static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+        self.coalesce_keys.insert(key.clone(), generation);
+        Coalesce { key, generation }
+    }
+
+    /// Whether a newer callback with the same coalescing key has been queued since `queued` was.
+    fn is_superseded(&self, queued: &QueuedCallback) -> bool {
+        queued.coalesce.as_ref().is_some_and(|coalesce| {
+            self.coalesce_keys
+                .get(&coalesce.key)
+                .is_some_and(|newest| *newest != coalesce.generation)
+        })
+    }
+
+    /// Issues a new ticket, which stays outstanding until [`complete_ticket`](Self::complete_ticket)
+    /// is called for it.
+    pub fn issue_ticket(&self) -> CallbackTicket {
+        static NEXT_TICKET: AtomicU64 = AtomicU64::new(0);
+        let ticket = CallbackTicket(NEXT_TICKET.fetch_add(1, Ordering::Relaxed));
+        self.pending_tickets.insert(ticket);
+        ticket
+    }
+
+    pub fn complete_ticket(&self, ticket: CallbackTicket) {
+        self.pending_tickets.remove(&ticket);
+    }
+
+    /// Whether the callback holding `ticket` has run (or been discarded).
+    pub fn is_ticket_complete(&self, ticket: CallbackTicket) -> bool {
+        !self.pending_tickets.contains(&ticket)
+    }
+
+    /// Whether every callback which `queued` must run after has run.
+    pub fn is_ready(&self, queued: &QueuedCallback) -> bool {
+        queued
+            .after
+            .iter()
+            .all(|ticket| self.is_ticket_complete(*ticket))
+    }
+
+    /// Takes the next callback queued for `schedule`, serving the priority lanes by weight.
+    /// Callbacks superseded by a newer one with the same coalescing key are discarded.
+    pub fn try_recv(&self, schedule: InternedScheduleLabel) -> Option<QueuedCallback> {
+        loop {
+            let queued = self.with_existing_queue(schedule, ScheduleQueue::pop)??;
+            if self.is_superseded(&queued) {
+                if let Some(ticket) = queued.ticket {
+                    self.complete_ticket(ticket);
+                }
+                continue;
+            }
+            if let Some(coalesce) = &queued.coalesce {
+                self.coalesce_keys
+                    .remove_if(&coalesce.key, |_, newest| *newest == coalesce.generation);
+            }
+            return Some(queued);
+        }
+    }
+
+    /// Puts callbacks back at the front of their lanes in a schedule's queue, in order, to be run by
+    /// the pump in a later frame.
+    pub fn defer(
         &self,
         schedule: InternedScheduleLabel,
-    ) -> tokio::sync::mpsc::UnboundedSender<MainThreadCallback> {
-        self.channels
-            .entry(schedule)
-            .or_default()
-            .value()
-            .task_tx
-            .clone()
+        callbacks: impl IntoIterator<Item = QueuedCallback>,
+    ) {
+        let mut by_lane: [VecDeque<_>; 3] = Default::default();
+        for queued in callbacks {
+            by_lane[queued.priority.lane()].push_back(queued);
+        }
+        self.with_queue(schedule, |queue| {
+            let mut pump = queue.pump();
+            for (deferred, mut callbacks) in pump.deferred.iter_mut().zip(by_lane) {
+                callbacks.append(deferred);
+                *deferred = callbacks;
+            }
+        });
+    }
+
+    /// Returns how many callbacks are currently queued for `schedule`, including ones the pump has
+    /// carried over from earlier frames.
+    pub fn len(&self, schedule: InternedScheduleLabel) -> usize {
+        self.with_existing_queue(schedule, ScheduleQueue::len)
+            .unwrap_or(0)
+    }
+
+    /// Returns how many callbacks are currently queued across every schedule.
+    pub fn total_len(&self) -> usize {
+        self.queue_lengths().into_iter().map(|(_, len)| len).sum()
+    }
+
+    /// Returns when the longest waiting callback queued for `schedule` was submitted, or `None` if
+    /// nothing is queued. Systems can compare this against [`Instant::now`] to notice a backlog
+    /// building up, e.g. to hold off on spawning more tasks.
+    pub fn oldest_enqueued(&self, schedule: InternedScheduleLabel) -> Option<Instant> {
+        self.with_existing_queue(schedule, ScheduleQueue::oldest_queued_at)
+            .flatten()
+    }
+
+    /// Returns when the longest waiting callback in any schedule was submitted.
+    pub fn oldest_enqueued_overall(&self) -> Option<Instant> {
+        self.registered
+            .iter()
+            .map(|(_, queue)| queue.oldest_queued_at())
+            .chain(self.channels.iter().map(|queue| queue.oldest_queued_at()))
+            .flatten()
+            .min()
+    }
+
+    /// Returns how many callbacks are currently queued for each schedule which has been submitted to.
+    pub fn queue_lengths(&self) -> Vec<(InternedScheduleLabel, usize)> {
+        self.registered
+            .iter()
+            .map(|(schedule, queue)| (*schedule, queue.len()))
+            .chain(
+                self.channels
+                    .iter()
+                    .map(|queue| (*queue.key(), queue.len())),
+            )
+            .collect()
     }
 
-    pub fn try_recv(&self, schedule: InternedScheduleLabel) -> Option<MainThreadCallback> {
-        self.channels
-            .get_mut(&schedule)
-            .and_then(|mut channel_pair| channel_pair.task_rx.try_recv().ok())
+    /// Discards every queued callback in every schedule. The tickets of discarded callbacks count as
+    /// complete, so nothing waits on them forever.
+    pub fn clear(&self) {
+        for (_, queue) in self.registered.iter() {
+            queue.clear();
+        }
+        for queue in self.channels.iter() {
+            queue.clear();
+        }
+        self.pending_tickets.clear();
+        self.coalesce_keys.clear();
     }
 }
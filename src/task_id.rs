@@ -0,0 +1,28 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A unique identifier assigned to every task spawned through [`Tasks`](crate::Tasks).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TaskId(u64);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+impl TaskId {
+    pub(crate) fn next() -> Self {
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// How many task ids have been handed out since the program started.
+    pub fn allocated() -> u64 {
+        NEXT_ID.load(Ordering::Relaxed)
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for TaskId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "task#{}", self.0)
+    }
+}
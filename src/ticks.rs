@@ -1,7 +1,10 @@
 use bevy_app::{App, Last, Plugin};
-use bevy_ecs::system::{ResMut, Resource};
+use bevy_ecs::{
+    component::Tick,
+    system::{ResMut, Resource, SystemChangeTick},
+};
 use std::sync::{
-    atomic::{AtomicUsize, Ordering},
+    atomic::{AtomicU32, AtomicUsize, Ordering},
     Arc,
 };
 
@@ -10,6 +13,8 @@ use std::sync::{
 pub struct UpdateTicks {
     ticks: Arc<AtomicUsize>,
     tick_tx: tokio::sync::watch::Sender<()>,
+    world_change_tick: Arc<AtomicU32>,
+    sleepers: Arc<AtomicUsize>,
 }
 
 impl UpdateTicks {
@@ -21,29 +26,66 @@ impl UpdateTicks {
         self.ticks.clone()
     }
 
-    pub fn tick_rx(&self) -> tokio::sync::watch::Receiver<()> {
+    /// The world's change tick as of the end of the last update.
+    pub fn world_change_tick(&self) -> Tick {
+        Tick::new(self.world_change_tick.load(Ordering::SeqCst))
+    }
+
+    pub fn world_change_ticks(&self) -> Arc<AtomicU32> {
+        self.world_change_tick.clone()
+    }
+
+    pub(crate) fn tick_rx(&self) -> tokio::sync::watch::Receiver<()> {
         self.tick_tx.subscribe()
     }
 
+    /// The number of tasks currently waiting on the tick channel. Ticks are only broadcast while
+    /// this is non-zero.
+    pub fn sleepers(&self) -> Arc<AtomicUsize> {
+        self.sleepers.clone()
+    }
+
     fn increment_ticks(&self) -> usize {
-        let new_ticks = self.ticks.fetch_add(1, Ordering::SeqCst).wrapping_add(1);
-        let _ = self.tick_tx.send(());
+        // Only this system writes the tick count, so a plain store avoids a read-modify-write.
+        let new_ticks = self.ticks.load(Ordering::Relaxed).wrapping_add(1);
+        self.ticks.store(new_ticks, Ordering::SeqCst);
+        // Sleepers register before checking the tick count, so if this load sees no sleepers then any
+        // task which starts sleeping afterwards will observe the new tick count without a notification.
+        if self.sleepers.load(Ordering::SeqCst) > 0 {
+            let _ = self.tick_tx.send(());
+        }
         new_ticks
     }
 }
 
+/// Registers a task as waiting on the tick channel for as long as it's alive.
+pub(crate) struct TickSleeper {
+    sleepers: Arc<AtomicUsize>,
+}
+
+impl TickSleeper {
+    pub(crate) fn register(sleepers: &Arc<AtomicUsize>) -> Self {
+        sleepers.fetch_add(1, Ordering::SeqCst);
+        Self {
+            sleepers: sleepers.clone(),
+        }
+    }
+}
+
+impl Drop for TickSleeper {
+    fn drop(&mut self) {
+        self.sleepers.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 pub struct TicksPlugin;
 
 impl TicksPlugin {
-    fn increment_system(ticks: ResMut<UpdateTicks>) {
+    fn increment_system(ticks: ResMut<UpdateTicks>, change_tick: SystemChangeTick) {
+        ticks
+            .world_change_tick
+            .store(change_tick.this_run().get(), Ordering::SeqCst);
         ticks.increment_ticks();
-        // // Run as late as possible, by running in a command after Last.
-        // commands.add(|world: &mut World| {
-        //     world
-        //         .get_resource_mut::<UpdateTicks>()
-        //         .unwrap()
-        //         .increment_ticks();
-        // });
     }
 }
 
@@ -52,6 +94,8 @@ impl Plugin for TicksPlugin {
         app.insert_resource(UpdateTicks {
             ticks: Arc::new(AtomicUsize::new(0)),
             tick_tx: tokio::sync::watch::channel(()).0,
+            world_change_tick: Arc::new(AtomicU32::new(0)),
+            sleepers: Arc::new(AtomicUsize::new(0)),
         })
         .add_systems(Last, Self::increment_system);
     }
@@ -0,0 +1,232 @@
+use crate::{
+    context::main_thread::{MainThreadCallback, MainThreadContext, MainThreadRunConfiguration},
+    task_channels::{CallbackTicket, Priority, QueuedCallback, TaskChannels},
+    ticks::UpdateTicks,
+};
+use bevy_ecs::{
+    schedule::InternedScheduleLabel,
+    system::{Res, ResMut, Resource},
+};
+use bevy_utils::{Duration, Instant};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+enum Deadline {
+    At(Instant),
+    Tick(usize),
+}
+
+struct PendingTimer {
+    deadline: Deadline,
+    schedule: InternedScheduleLabel,
+    priority: Priority,
+    coalesce_key: Option<Arc<str>>,
+    after: Vec<CallbackTicket>,
+    run_at_tick: Option<usize>,
+    callback: MainThreadCallback,
+}
+
+/// Delayed main thread callbacks for ordinary systems. Callbacks scheduled here are handed to the
+/// same per-schedule queues as [`run_on_main_thread`](crate::TaskContext::run_on_main_thread)
+/// callbacks once they're due, so simple delayed effects don't need an async task.
+#[derive(Resource, Default)]
+pub struct MainThreadTimers {
+    pending: Vec<PendingTimer>,
+}
+
+impl MainThreadTimers {
+    /// Runs the callback in the default schedule once `delay` has elapsed.
+    pub fn after(
+        &mut self,
+        delay: Duration,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) {
+        self.after_with_config(delay, Default::default(), callback);
+    }
+
+    pub fn after_with_config(
+        &mut self,
+        delay: Duration,
+        config: MainThreadRunConfiguration,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) {
+        self.push(Deadline::At(Instant::now() + delay), config, callback);
+    }
+
+    /// Runs the callback in the default schedule once the update tick count reaches `tick`.
+    pub fn at_tick(
+        &mut self,
+        tick: usize,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) {
+        self.at_tick_with_config(tick, Default::default(), callback);
+    }
+
+    pub fn at_tick_with_config(
+        &mut self,
+        tick: usize,
+        config: MainThreadRunConfiguration,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) {
+        self.push(Deadline::Tick(tick), config, callback);
+    }
+
+    /// The number of callbacks which aren't due yet.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Drops every pending timer without running it.
+    pub(crate) fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    fn push(
+        &mut self,
+        deadline: Deadline,
+        config: MainThreadRunConfiguration,
+        callback: impl FnOnce(MainThreadContext) + Send + 'static,
+    ) {
+        self.pending.push(PendingTimer {
+            deadline,
+            schedule: config.schedule,
+            priority: config.priority,
+            coalesce_key: config.coalesce_key,
+            after: config.after,
+            run_at_tick: config.run_at_tick,
+            callback: Box::new(callback),
+        });
+    }
+
+    pub(crate) fn dispatch_system(
+        mut timers: ResMut<MainThreadTimers>,
+        ticks: Res<UpdateTicks>,
+        task_channels: Res<TaskChannels>,
+    ) {
+        if timers.pending.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let tick = ticks.tick();
+        let (due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut timers.pending)
+            .into_iter()
+            .partition(|timer| match timer.deadline {
+                Deadline::At(at) => at <= now,
+                Deadline::Tick(target) => target <= tick,
+            });
+        timers.pending = pending;
+        for timer in due {
+            // Pushing onto the lock-free schedule queues can't fail.
+            let _ = task_channels.submit_queued(
+                timer.schedule,
+                QueuedCallback {
+                    priority: timer.priority,
+                    coalesce: timer.coalesce_key.map(|key| task_channels.coalesce(key)),
+                    after: timer.after,
+                    run_at_tick: timer.run_at_tick,
+                    ..QueuedCallback::new(timer.callback)
+                },
+            );
+        }
+    }
+}
+
+/// How often a callback submitted with
+/// [`submit_repeating`](crate::TaskContext::submit_repeating) runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepeatBy {
+    /// Every `n` update ticks.
+    Frames(usize),
+    /// Every time this much wall-clock time has elapsed.
+    Duration(Duration),
+}
+
+impl From<Duration> for RepeatBy {
+    fn from(every: Duration) -> Self {
+        RepeatBy::Duration(every)
+    }
+}
+
+/// Keeps a repeating callback running. The callback stops repeating when the handle is dropped or
+/// [`cancel`](Self::cancel) is called; use [`detach`](Self::detach) to keep it running forever.
+#[must_use = "the callback stops repeating when the handle is dropped"]
+pub struct RepeatHandle {
+    cancelled: Arc<AtomicBool>,
+    detached: bool,
+}
+
+impl RepeatHandle {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Drops the handle without cancelling the callback.
+    pub fn detach(mut self) {
+        self.detached = true;
+    }
+}
+
+impl Drop for RepeatHandle {
+    fn drop(&mut self) {
+        if !self.detached {
+            self.cancel();
+        }
+    }
+}
+
+pub(crate) struct Repeating {
+    every: RepeatBy,
+    cancelled: Arc<AtomicBool>,
+    callback: Box<dyn FnMut(MainThreadContext) + Send + 'static>,
+}
+
+impl Repeating {
+    pub(crate) fn new(
+        every: RepeatBy,
+        callback: impl FnMut(MainThreadContext) + Send + 'static,
+    ) -> (Self, RepeatHandle) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let repeating = Self {
+            every,
+            cancelled: cancelled.clone(),
+            callback: Box::new(callback),
+        };
+        let handle = RepeatHandle {
+            cancelled,
+            detached: false,
+        };
+        (repeating, handle)
+    }
+
+    /// Schedules the next run, which runs the callback and then schedules the one after it.
+    pub(crate) fn schedule(mut self, timers: &mut MainThreadTimers, current_tick: usize) {
+        let next = move |ctx: MainThreadContext| {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            let mut ctx = ctx;
+            (self.callback)(ctx.reborrow());
+            if !self.cancelled.load(Ordering::Relaxed) {
+                let current_tick = ctx.current_tick;
+                self.schedule(
+                    &mut ctx.world.resource_mut::<MainThreadTimers>(),
+                    current_tick,
+                );
+            }
+        };
+        match self.every {
+            RepeatBy::Frames(frames) => timers.at_tick(current_tick + frames.max(1), next),
+            RepeatBy::Duration(every) => timers.after(every, next),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use crate::task_id::TaskId;
+use bevy_ecs::system::Resource;
+use bevy_utils::Duration;
+use dashmap::DashMap;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+};
+
+/// Per-task CPU time accounting. Every task spawned through [`Tasks`](crate::Tasks) while this
+/// resource exists has the time spent inside its `poll` recorded, so that background CPU cost can be
+/// attributed to specific jobs. The resource is inserted by [`TasksPlugin`](crate::TasksPlugin) when
+/// the `task-timing` feature is enabled.
+#[derive(Resource, Clone, Default)]
+pub struct TaskTimings {
+    timings: Arc<DashMap<TaskId, Arc<TaskTiming>>>,
+    /// The busy time of every task ever tracked, including ones whose timings were dropped.
+    total_busy_nanos: Arc<AtomicU64>,
+}
+
+/// The accumulated poll statistics of a single task.
+#[derive(Default, Debug)]
+pub struct TaskTiming {
+    busy_nanos: AtomicU64,
+    polls: AtomicU64,
+    finished: AtomicBool,
+}
+
+impl TaskTiming {
+    /// Total time spent polling the task's future.
+    pub fn busy_time(&self) -> Duration {
+        Duration::from_nanos(self.busy_nanos.load(Ordering::Relaxed))
+    }
+
+    /// How many times the task's future has been polled.
+    pub fn polls(&self) -> u64 {
+        self.polls.load(Ordering::Relaxed)
+    }
+
+    /// Whether the task's future has completed, or was dropped after panicking or being aborted.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+}
+
+impl TaskTimings {
+    pub fn get(&self, id: TaskId) -> Option<Arc<TaskTiming>> {
+        self.timings.get(&id).map(|timing| timing.value().clone())
+    }
+
+    pub fn busy_time(&self, id: TaskId) -> Option<Duration> {
+        self.timings.get(&id).map(|timing| timing.busy_time())
+    }
+
+    /// Total time spent polling every task tracked so far, including ones whose timings have been
+    /// dropped since.
+    pub fn total_busy_time(&self) -> Duration {
+        Duration::from_nanos(self.total_busy_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Returns a snapshot of the timings of every tracked task.
+    pub fn snapshot(&self) -> Vec<(TaskId, Arc<TaskTiming>)> {
+        self.timings
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Drops the timings of every task that has completed. Timings of finished tasks are otherwise
+    /// kept as long as the task's [`TaskRegistry`](crate::TaskRegistry) entry, so that short-lived
+    /// jobs can still be inspected after they end.
+    pub fn clear_finished(&self) {
+        self.timings.retain(|_, timing| !timing.is_finished());
+    }
+
+    /// Drops the timings of finished tasks, except for those that `keep` returns `true` for.
+    pub(crate) fn retain_finished(&self, keep: impl Fn(TaskId) -> bool) {
+        self.timings
+            .retain(|id, timing| !timing.is_finished() || keep(*id));
+    }
+
+    #[cfg(feature = "task-timing")]
+    pub(crate) fn track<Task: std::future::Future>(
+        &self,
+        id: TaskId,
+        task: Task,
+    ) -> impl std::future::Future<Output = Task::Output> {
+        let timing = self.timings.entry(id).or_default().value().clone();
+        let total_busy_nanos = self.total_busy_nanos.clone();
+        // Aborted and panicked tasks are never ready, but their futures are dropped.
+        let finished = MarkFinishedOnDrop(timing.clone());
+        let mut task = Box::pin(task);
+        futures_util::future::poll_fn(move |cx| {
+            let _finished = &finished;
+            let start = bevy_utils::Instant::now();
+            let poll = task.as_mut().poll(cx);
+            let elapsed = start.elapsed().as_nanos() as u64;
+            timing.busy_nanos.fetch_add(elapsed, Ordering::Relaxed);
+            total_busy_nanos.fetch_add(elapsed, Ordering::Relaxed);
+            timing.polls.fetch_add(1, Ordering::Relaxed);
+            if poll.is_ready() {
+                timing.finished.store(true, Ordering::Relaxed);
+            }
+            poll
+        })
+    }
+}
+
+/// Marks a task's timing as finished once its future is dropped.
+#[cfg(feature = "task-timing")]
+struct MarkFinishedOnDrop(Arc<TaskTiming>);
+
+#[cfg(feature = "task-timing")]
+impl Drop for MarkFinishedOnDrop {
+    fn drop(&mut self) {
+        self.0.finished.store(true, Ordering::Relaxed);
+    }
+}
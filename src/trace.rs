@@ -0,0 +1,87 @@
+use crate::task_id::TaskId;
+use bevy_ecs::{schedule::InternedScheduleLabel, system::Resource};
+use bevy_utils::{tracing::error, Duration};
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    sync::{Arc, Mutex},
+};
+
+/// One executed main thread callback, as recorded by [`CallbackTrace`].
+#[derive(Clone, Debug)]
+pub struct CallbackTraceEntry {
+    /// The task which submitted the callback, if it came from a spawned task.
+    pub task: Option<TaskId>,
+    pub schedule: InternedScheduleLabel,
+    pub tick: usize,
+    pub duration: Duration,
+}
+
+/// An opt-in ring buffer of the most recently executed main thread callbacks, giving a post-mortem
+/// timeline of what mutated the world from tasks. Enable it with
+/// [`TasksPlugin::with_callback_trace`](crate::TasksPlugin::with_callback_trace).
+#[derive(Resource, Clone)]
+pub struct CallbackTrace {
+    capacity: usize,
+    entries: Arc<Mutex<VecDeque<CallbackTraceEntry>>>,
+}
+
+impl CallbackTrace {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+        }
+    }
+
+    pub(crate) fn record(&self, entry: CallbackTraceEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Returns the recorded entries, oldest first.
+    pub fn entries(&self) -> Vec<CallbackTraceEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+        entries.iter().cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clear();
+    }
+
+    /// Formats the recorded entries as a human-readable timeline, oldest first.
+    pub fn dump(&self) -> String {
+        let mut dump = String::new();
+        for entry in self.entries() {
+            let task = entry
+                .task
+                .map_or_else(|| "-".to_string(), |task| task.to_string());
+            let _ = writeln!(
+                dump,
+                "tick {:>8} {:<16?} {:<12} {:?}",
+                entry.tick, entry.schedule, task, entry.duration
+            );
+        }
+        dump
+    }
+
+    /// Installs a process-wide panic hook which logs the trace as an error before running the
+    /// previously installed hook. The hook stays installed for the rest of the process.
+    pub fn install_panic_hook(&self) {
+        let trace = self.clone();
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            error!("Recent main thread callbacks:\n{}", trace.dump());
+            previous(info);
+        }));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::{
+    join::AbortHandle, task_channels::TaskChannels, task_id::TaskId, timers::MainThreadTimers,
+};
+use bevy_ecs::{
+    event::{Event, EventReader},
+    system::{Res, ResMut, Resource},
+    world::{Command, World},
+};
+use dashmap::DashMap;
+use std::{any::Any, sync::Arc};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Keeps an [`AbortHandle`] for every task spawned through [`Tasks`](crate::Tasks) which hasn't yet
+/// finished, so that running tasks can be aborted without holding on to their
+/// [`JoinHandle`](crate::JoinHandle)s.
+#[derive(Resource, Clone, Default)]
+pub struct TrackedTasks {
+    tasks: Arc<DashMap<TaskId, AbortHandle>>,
+    /// The inbox senders of tasks spawned with [`spawn_with_inbox`](crate::Tasks::spawn_with_inbox).
+    inboxes: Arc<DashMap<TaskId, Box<dyn Any + Send + Sync>>>,
+}
+
+impl TrackedTasks {
+    /// The number of tracked tasks which are still running.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    pub fn contains(&self, id: TaskId) -> bool {
+        self.tasks.contains_key(&id)
+    }
+
+    /// Returns the ids and abort handles of every tracked task, in spawn order.
+    pub fn snapshot(&self) -> Vec<(TaskId, AbortHandle)> {
+        let mut tasks: Vec<_> = self
+            .tasks
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        tasks.sort_by_key(|(id, _)| *id);
+        tasks
+    }
+
+    /// Aborts the task with the given id, returning whether it was still running.
+    pub fn abort(&self, id: TaskId) -> bool {
+        match self.tasks.remove(&id) {
+            Some((_, handle)) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Aborts every tracked task.
+    pub fn abort_all(&self) {
+        let ids: Vec<TaskId> = self.tasks.iter().map(|entry| *entry.key()).collect();
+        for id in ids {
+            self.abort(id);
+        }
+    }
+
+    /// Returns a sender for the inbox of the running task with the given id, if it was spawned with
+    /// [`spawn_with_inbox`](crate::Tasks::spawn_with_inbox) for messages of type `M`.
+    pub fn inbox<M: Send + 'static>(&self, id: TaskId) -> Option<UnboundedSender<M>> {
+        self.inboxes
+            .get(&id)?
+            .downcast_ref::<UnboundedSender<M>>()
+            .cloned()
+    }
+
+    pub(crate) fn insert(&self, id: TaskId, handle: AbortHandle) {
+        self.tasks.insert(id, handle);
+    }
+
+    pub(crate) fn insert_inbox<M: Send + 'static>(
+        &self,
+        id: TaskId,
+        message_tx: UnboundedSender<M>,
+    ) {
+        self.inboxes.insert(id, Box::new(message_tx));
+    }
+
+    pub(crate) fn remove(&self, id: TaskId) {
+        self.tasks.remove(&id);
+        self.inboxes.remove(&id);
+    }
+
+    /// Returns a guard which stops tracking the task once dropped, which happens when the task's
+    /// future completes or is aborted.
+    pub(crate) fn untrack_on_drop(&self, id: TaskId) -> UntrackOnDrop {
+        UntrackOnDrop {
+            tasks: self.clone(),
+            id,
+        }
+    }
+}
+
+pub(crate) struct UntrackOnDrop {
+    tasks: TrackedTasks,
+    id: TaskId,
+}
+
+impl Drop for UntrackOnDrop {
+    fn drop(&mut self) {
+        self.tasks.remove(self.id);
+    }
+}
+
+/// Aborts every tracked task and clears every queued or delayed main thread callback, including
+/// ones scheduled through [`MainThreadTimers`] and repeating callbacks. Can be queued as a
+/// [`Command`] or sent as an [`Event`], in which case the reset happens at the start of the next frame.
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct ResetTasks;
+
+impl Command for ResetTasks {
+    fn apply(self, world: &mut World) {
+        world.resource::<TrackedTasks>().abort_all();
+        world.resource::<TaskChannels>().clear();
+        world.resource_mut::<MainThreadTimers>().clear();
+    }
+}
+
+pub(crate) fn reset_tasks_on_event(
+    mut events: EventReader<ResetTasks>,
+    tracked: Res<TrackedTasks>,
+    task_channels: Res<TaskChannels>,
+    mut timers: ResMut<MainThreadTimers>,
+) {
+    if events.read().count() > 0 {
+        tracked.abort_all();
+        task_channels.clear();
+        timers.clear();
+    }
+}
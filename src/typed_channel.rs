@@ -0,0 +1,118 @@
+use crate::inbox::Inbox;
+use bevy_app::App;
+use bevy_ecs::system::{Res, ResMut, Resource, SystemParam};
+use dashmap::DashMap;
+use std::{
+    any::{Any, TypeId},
+    sync::Arc,
+};
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// The task side of every channel registered with [`InitTaskChannel::init_task_channel`], shared
+/// with tasks through their [`TaskContext`](crate::TaskContext).
+#[derive(Resource, Clone, Default)]
+pub struct TypedChannels {
+    channels: Arc<DashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+/// The ends of a typed channel which tasks use: an inbox of messages sent by systems, and a sender
+/// of messages to systems.
+struct TaskEnds<T> {
+    inbox: Inbox<T>,
+    to_systems_tx: UnboundedSender<T>,
+}
+
+impl TypedChannels {
+    pub(crate) fn inbox<T: Send + 'static>(&self) -> Option<Inbox<T>> {
+        Some(self.task_ends::<T>()?.inbox.clone())
+    }
+
+    pub(crate) fn sender<T: Send + 'static>(&self) -> Option<UnboundedSender<T>> {
+        Some(self.task_ends::<T>()?.to_systems_tx.clone())
+    }
+
+    fn task_ends<T: Send + 'static>(&self) -> Option<Arc<TaskEnds<T>>> {
+        let ends = self.channels.get(&TypeId::of::<T>())?.clone();
+        ends.downcast().ok()
+    }
+}
+
+/// A typed channel between systems and tasks, created by
+/// [`InitTaskChannel::init_task_channel`]. Systems use it through [`TaskSender`] and
+/// [`TaskReceiver`].
+#[derive(Resource)]
+pub struct TaskChannel<T: Send + 'static> {
+    to_tasks_tx: UnboundedSender<T>,
+    from_tasks_rx: UnboundedReceiver<T>,
+}
+
+/// Sends messages of type `T` from a system to the tasks reading
+/// [`TaskContext::channel_inbox`](crate::TaskContext::channel_inbox).
+#[derive(SystemParam)]
+pub struct TaskSender<'w, T: Send + 'static> {
+    channel: Res<'w, TaskChannel<T>>,
+}
+
+impl<'w, T: Send + 'static> TaskSender<'w, T> {
+    /// Sends a message to the tasks. Each message is received by exactly one task.
+    pub fn send(&self, message: T) {
+        // The tasks' receiver lives as long as the channel resource, so this can't fail.
+        let _ = self.channel.to_tasks_tx.send(message);
+    }
+
+    /// A sender which can be moved out of the system, e.g. into a task.
+    pub fn sender(&self) -> UnboundedSender<T> {
+        self.channel.to_tasks_tx.clone()
+    }
+}
+
+/// Receives messages of type `T` in a system which tasks sent through
+/// [`TaskContext::channel_sender`](crate::TaskContext::channel_sender).
+#[derive(SystemParam)]
+pub struct TaskReceiver<'w, T: Send + 'static> {
+    channel: ResMut<'w, TaskChannel<T>>,
+}
+
+impl<'w, T: Send + 'static> TaskReceiver<'w, T> {
+    /// Returns the next message if one has been sent.
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.channel.from_tasks_rx.try_recv().ok()
+    }
+
+    /// Returns every message sent since the last time the channel was read.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+}
+
+/// Registers typed channels between systems and tasks.
+pub trait InitTaskChannel {
+    /// Creates a channel of messages of type `T` in both directions: systems send with
+    /// [`TaskSender<T>`] and tasks receive with
+    /// [`TaskContext::channel_inbox`](crate::TaskContext::channel_inbox), while tasks send with
+    /// [`TaskContext::channel_sender`](crate::TaskContext::channel_sender) and systems receive with
+    /// [`TaskReceiver<T>`]. Must be called after the [`TasksPlugin`](crate::TasksPlugin) is added.
+    fn init_task_channel<T: Send + 'static>(&mut self) -> &mut Self;
+}
+
+impl InitTaskChannel for App {
+    fn init_task_channel<T: Send + 'static>(&mut self) -> &mut Self {
+        if self.world().contains_resource::<TaskChannel<T>>() {
+            return self;
+        }
+        let (inbox, to_tasks_tx) = Inbox::new();
+        let (to_systems_tx, from_tasks_rx) = tokio::sync::mpsc::unbounded_channel();
+        let ends = TaskEnds {
+            inbox,
+            to_systems_tx,
+        };
+        self.world()
+            .resource::<TypedChannels>()
+            .channels
+            .insert(TypeId::of::<T>(), Arc::new(ends));
+        self.insert_resource(TaskChannel {
+            to_tasks_tx,
+            from_tasks_rx,
+        })
+    }
+}
@@ -0,0 +1,197 @@
+use crate::Tasks;
+use bevy_app::{App, Plugin, Startup};
+use bevy_ecs::{event::Event, system::Resource};
+use std::{io, marker::PhantomData, net::SocketAddr, sync::Mutex};
+use tokio::{net::UdpSocket, sync::mpsc};
+
+/// A message which can be sent and received as a single UDP datagram.
+pub trait DatagramMessage: Send + Sync + 'static {
+    fn encode(&self) -> Vec<u8>;
+    /// Decodes a received datagram, returning `None` to drop datagrams which aren't valid messages.
+    fn decode(bytes: &[u8]) -> Option<Self>
+    where
+        Self: Sized;
+}
+
+impl DatagramMessage for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+/// Sent for each datagram received by the [`UdpPlugin`] socket which decoded successfully.
+#[derive(Event, Clone, Debug)]
+pub struct UdpReceived<M> {
+    pub from: SocketAddr,
+    pub message: M,
+}
+
+/// Lifecycle events for the [`UdpPlugin`] socket.
+#[derive(Event, Clone, Debug)]
+pub enum UdpConnectionEvent {
+    /// The socket was bound, and connected to the remote address if one was configured.
+    Connected {
+        local: SocketAddr,
+        remote: Option<SocketAddr>,
+    },
+    /// The socket failed and the driver task has stopped.
+    Disconnected { error: String },
+}
+
+struct Outgoing<M> {
+    to: Option<SocketAddr>,
+    message: M,
+}
+
+/// Queues messages to be sent by the [`UdpPlugin`] driver task.
+#[derive(Resource)]
+pub struct UdpSender<M> {
+    outgoing_tx: mpsc::UnboundedSender<Outgoing<M>>,
+}
+
+impl<M> Clone for UdpSender<M> {
+    fn clone(&self) -> Self {
+        Self {
+            outgoing_tx: self.outgoing_tx.clone(),
+        }
+    }
+}
+
+impl<M: DatagramMessage> UdpSender<M> {
+    /// Sends a message to the configured remote address. Returns `false` if the driver task has
+    /// stopped.
+    pub fn send(&self, message: M) -> bool {
+        self.outgoing_tx
+            .send(Outgoing { to: None, message })
+            .is_ok()
+    }
+
+    /// Sends a message to the given address. Returns `false` if the driver task has stopped.
+    pub fn send_to(&self, to: SocketAddr, message: M) -> bool {
+        self.outgoing_tx
+            .send(Outgoing {
+                to: Some(to),
+                message,
+            })
+            .is_ok()
+    }
+}
+
+/// Binds a UDP socket owned by a background task on the crate's runtime. Systems send messages with
+/// the [`UdpSender<M>`] resource and receive them as [`UdpReceived<M>`] events. Requires the
+/// [`TasksPlugin`](crate::TasksPlugin) to be added first.
+pub struct UdpPlugin<M> {
+    pub bind: SocketAddr,
+    /// If set, the socket is connected to this address and [`UdpSender::send`] targets it.
+    pub remote: Option<SocketAddr>,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> UdpPlugin<M> {
+    pub fn new(bind: SocketAddr) -> Self {
+        Self {
+            bind,
+            remote: None,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn connected_to(mut self, remote: SocketAddr) -> Self {
+        self.remote = Some(remote);
+        self
+    }
+}
+
+impl<M: DatagramMessage> UdpPlugin<M> {
+    fn spawn_driver(
+        tasks: &Tasks,
+        bind: SocketAddr,
+        remote: Option<SocketAddr>,
+        mut outgoing_rx: mpsc::UnboundedReceiver<Outgoing<M>>,
+    ) {
+        tasks.spawn_tokio(move |ctx| async move {
+            let socket = match Self::open(bind, remote).await {
+                Ok(socket) => socket,
+                Err(err) => {
+                    let error = err.to_string();
+                    ctx.submit_on_main_thread(move |ctx| {
+                        ctx.world
+                            .send_event(UdpConnectionEvent::Disconnected { error });
+                    });
+                    return;
+                }
+            };
+            if let Ok(local) = socket.local_addr() {
+                ctx.submit_on_main_thread(move |ctx| {
+                    ctx.world
+                        .send_event(UdpConnectionEvent::Connected { local, remote });
+                });
+            }
+
+            let mut buf = vec![0; 65536];
+            let error = loop {
+                tokio::select! {
+                    received = socket.recv_from(&mut buf) => match received {
+                        Ok((len, from)) => {
+                            if let Some(message) = M::decode(&buf[..len]) {
+                                ctx.submit_on_main_thread(move |ctx| {
+                                    ctx.world.send_event(UdpReceived { from, message });
+                                });
+                            }
+                        }
+                        // Reported on some platforms when a previous send was refused by the peer.
+                        Err(err) if err.kind() == io::ErrorKind::ConnectionReset => {}
+                        Err(err) => break err.to_string(),
+                    },
+                    outgoing = outgoing_rx.recv() => {
+                        let Some(Outgoing { to, message }) = outgoing else {
+                            return;
+                        };
+                        let bytes = message.encode();
+                        let sent = match to {
+                            Some(to) => socket.send_to(&bytes, to).await,
+                            None => socket.send(&bytes).await,
+                        };
+                        if let Err(err) = sent {
+                            if err.kind() != io::ErrorKind::ConnectionRefused {
+                                break err.to_string();
+                            }
+                        }
+                    }
+                }
+            };
+            ctx.submit_on_main_thread(move |ctx| {
+                ctx.world
+                    .send_event(UdpConnectionEvent::Disconnected { error });
+            });
+        });
+    }
+
+    async fn open(bind: SocketAddr, remote: Option<SocketAddr>) -> io::Result<UdpSocket> {
+        let socket = UdpSocket::bind(bind).await?;
+        if let Some(remote) = remote {
+            socket.connect(remote).await?;
+        }
+        Ok(socket)
+    }
+}
+
+impl<M: DatagramMessage> Plugin for UdpPlugin<M> {
+    fn build(&self, app: &mut App) {
+        let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel();
+        let outgoing_rx = Mutex::new(Some(outgoing_rx));
+        let (bind, remote) = (self.bind, self.remote);
+        app.insert_resource(UdpSender::<M> { outgoing_tx })
+            .add_event::<UdpReceived<M>>()
+            .add_event::<UdpConnectionEvent>()
+            .add_systems(Startup, move |tasks: Tasks| {
+                if let Some(outgoing_rx) = outgoing_rx.lock().unwrap().take() {
+                    Self::spawn_driver(&tasks, bind, remote, outgoing_rx);
+                }
+            });
+    }
+}